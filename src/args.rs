@@ -1,11 +1,27 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+use image::Rgb;
+
+use crate::common::color::parse_color;
+use crate::common::range::{parse_range, ValueRange};
+use crate::common::size::{parse_size, Size};
+use crate::common::topic_pair::{parse_topic_pair, TopicPair};
 
 #[derive(Debug, Parser)]
 #[clap(author, version, about, long_about=None)]
 pub struct Args {
     /// Path to the bag file.
+    #[clap(
+        required_unless_present = "list-encodings",
+        default_value_t,
+        hide_default_value = true
+    )]
     pub path_to_bag: String,
     /// Path to output directory.
+    #[clap(
+        required_unless_present = "list-encodings",
+        default_value_t,
+        hide_default_value = true
+    )]
     pub output_dir: String,
     /// The name of the topics from which you want to export images.
     pub topics: Vec<String>,
@@ -24,10 +40,230 @@ pub struct Args {
     /// Step by which frames should be exported.
     #[clap(short = 'S', long, default_value_t = 1u32)]
     pub step: u32,
-    /// Convert RGB8 to BGR8 (for case cv_bridge mixed up color channels)
-    #[clap(short)]
-    pub invert_channels: bool,
+    /// Convert RGB8 to BGR8 and vice versa in all topics
+    /// (for case cv_bridge mixed up color channels)
+    #[clap(short = 'i')]
+    pub invert_all_channels: bool,
+    /// Topic with mixed up color channels, e.g. `bgr8` images labelled as `rgb8`
+    /// by cv_bridge. Can be specified multiple times, supports regex with `-r`
+    /// [optional]
+    #[clap(long, value_name = "TOPIC")]
+    pub invert_channels: Vec<String>,
     /// Enable regular expressions in topic names
     #[clap(short, long)]
     pub regex: bool,
+    /// Output format for depth images (`16UC1` and `32FC1`)
+    #[clap(long, value_enum, default_value_t = DepthFormat::Png16)]
+    pub depth_format: DepthFormat,
+    /// Units of the exported depth values.
+    /// 16-bit PNG depth is always in millimetres
+    /// [optional]
+    #[clap(long, value_enum)]
+    pub depth_units: Option<DepthUnits>,
+    /// Demosaicing algorithm for raw `bayer_*` images
+    #[clap(long, value_enum, default_value_t = DebayerMethod::Bilinear)]
+    pub debayer: DebayerMethod,
+    /// Flatten transparent images (`rgba8`, `bgra8`) onto the background color
+    /// specified as `#RRGGBB` or `R,G,B`. If it's not specified, alpha channel is kept
+    /// [optional]
+    #[clap(long, value_parser = parse_color)]
+    pub background: Option<Rgb<u8>>,
+    /// Color matrix for YUV images (`yuv422`, `uyvy`, `yuyv`, `nv12`, `nv21`, `i420`)
+    #[clap(long, value_enum, default_value_t = YuvMatrix::Bt601)]
+    pub yuv_matrix: YuvMatrix,
+    /// Render float images (`32FC1`, `64FC1`, `32SC1`) and depth images as viewable
+    /// 8-bit images with values from the range: `auto` (min and max of every frame),
+    /// `MIN:MAX` (fixed range) or `pLOW:pHIGH` (percentiles of every frame, e.g. `p1:p99`)
+    /// [optional]
+    #[clap(long, value_parser = parse_range, allow_hyphen_values = true)]
+    pub range: Option<ValueRange>,
+    /// Color of NaN and infinite values in rendered float images, specified as
+    /// `#RRGGBB` or `R,G,B` [default: #000000]
+    #[clap(long, value_parser = parse_color)]
+    pub nan_color: Option<Rgb<u8>>,
+    /// Use logarithmic scale when rendering float images
+    #[clap(long)]
+    pub log_scale: bool,
+    /// Export every channel of generic OpenCV type images (e.g. `8UC(N)`, `16UC(N)`)
+    /// as a separate grayscale image, or every three channels as a false color image.
+    /// Required for images with more than 4 channels
+    /// [optional]
+    #[clap(long, value_enum)]
+    pub split_channels: Option<SplitChannels>,
+    /// Output format for optical flow images (`32FC2`)
+    #[clap(long, value_enum, default_value_t = FlowFormat::Png)]
+    pub flow_format: FlowFormat,
+    /// Flow magnitude (in pixels) rendered with the full brightness.
+    /// If it's not specified, the maximum magnitude of every frame is used
+    /// [optional]
+    #[clap(long)]
+    pub flow_max: Option<f32>,
+    /// Thermal camera topic with radiometric counts in `mono16` images.
+    /// Can be specified multiple times, supports regex with `-r`
+    /// [optional]
+    #[clap(long, value_name = "TOPIC")]
+    pub thermal: Vec<String>,
+    /// Gain of the linear calibration from thermal camera counts to degrees Celsius
+    #[clap(long, default_value_t = 0.01f64)]
+    pub thermal_gain: f64,
+    /// Offset of the linear calibration from thermal camera counts to degrees Celsius
+    #[clap(long, default_value_t = -273.15f64, allow_hyphen_values = true)]
+    pub thermal_offset: f64,
+    /// Color palette for thermal images
+    #[clap(long, value_enum, default_value_t = Palette::Ironbow)]
+    pub thermal_palette: Palette,
+    /// Temperature range (in degrees Celsius) of thermal images: `auto` (min and max
+    /// of every frame), `MIN:MAX` (fixed range) or `pLOW:pHIGH` (percentiles of every frame)
+    /// [default: auto]
+    #[clap(long, value_parser = parse_range, allow_hyphen_values = true)]
+    pub thermal_range: Option<ValueRange>,
+    /// Also write the temperature of thermal images (in degrees Celsius) to PFM files
+    #[clap(long)]
+    pub thermal_pfm: bool,
+    /// Export `stereo_msgs/DisparityImage` as metric depth (`fT / d`)
+    /// instead of colorized disparity
+    #[clap(long)]
+    pub disparity_depth: bool,
+    /// Output format of images. If it's not specified, images are saved as PNG
+    /// and compressed images are saved as is, without re-encoding
+    /// [optional]
+    #[clap(long, value_enum)]
+    pub format: Option<OutputFormat>,
+    /// Quality of JPEG images, from 1 to 100
+    #[clap(long, default_value_t = 95u8, value_parser = clap::value_parser!(u8).range(1..=100))]
+    pub jpeg_quality: u8,
+    /// Resize images to `WIDTHxHEIGHT`. Specify only `WIDTHx` or `xHEIGHT`
+    /// to keep the aspect ratio. Depth images are never resized
+    /// [optional]
+    #[clap(long, value_parser = parse_size)]
+    pub resize: Option<Size>,
+    /// Fields of organized `sensor_msgs/PointCloud2` exported as images, e.g. `range,intensity`.
+    /// `range` is calculated from `x`, `y` and `z` if the cloud doesn't have it.
+    /// If it's not specified, all fields except `x`, `y` and `z` are exported
+    /// [optional]
+    #[clap(long, value_name = "FIELD", value_delimiter = ',')]
+    pub cloud_fields: Vec<String>,
+    /// Output format of `foxglove_msgs/CompressedVideo` streams
+    #[clap(long, value_enum, default_value_t = VideoFormat::Mp4)]
+    pub video_format: VideoFormat,
+    /// Draw bounding boxes, class ids and scores from the `vision_msgs/Detection2DArray`
+    /// topic onto the images, specified as `IMAGE_TOPIC=DETECTIONS_TOPIC`.
+    /// Detections are matched to the images by header stamp. Can be specified
+    /// multiple times, image topics support regex with `-r`
+    /// [optional]
+    #[clap(long, value_name = "IMAGE=DETECTIONS", value_parser = parse_topic_pair)]
+    pub detections: Vec<TopicPair>,
+    /// Export the detections from `--detections` as dataset labels of the images
+    /// instead of drawing them. Frames of the paired topics without detections are skipped.
+    /// Numeric class ids are kept as class indices and COCO category ids, so class `0`
+    /// gets category id 0, other classes are sorted by name
+    /// [optional]
+    #[clap(long, value_enum)]
+    pub labels: Option<LabelFormat>,
+    /// Semantic segmentation topic with class ids in `mono8`, `8UC1`, `mono16` or `16UC1`
+    /// images, which are rendered with the colors of the classes.
+    /// Can be specified multiple times, supports regex with `-r`
+    /// [optional]
+    #[clap(long, value_name = "TOPIC")]
+    pub segmentation: Vec<String>,
+    /// Palette file of the segmentation classes with a line `ID COLOR NAME` per class,
+    /// the color is `#RRGGBB`, `R,G,B` or `none`. Classes missing from the file
+    /// get the colors of the PASCAL VOC palette
+    /// [optional]
+    #[clap(long, value_name = "FILE")]
+    pub seg_palette: Option<String>,
+    /// Blend the segmentation masks onto the images of another topic, specified as
+    /// `MASK_TOPIC=IMAGE_TOPIC`. Masks are matched to the images by header stamp.
+    /// Can be specified multiple times, mask topics support regex with `-r`
+    /// [optional]
+    #[clap(long, value_name = "MASK=IMAGE", value_parser = parse_topic_pair)]
+    pub seg_overlay: Vec<TopicPair>,
+    /// Opacity of the segmentation masks blended onto the images, from 0 to 1
+    #[clap(long, default_value_t = 0.5f32)]
+    pub seg_alpha: f32,
+    /// Print all supported image encodings and exit
+    #[clap(long, exclusive = true)]
+    pub list_encodings: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DepthFormat {
+    /// 16-bit grayscale PNG with depth in millimetres
+    Png16,
+    /// Portable float map with depth as 32-bit floats
+    Pfm,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DepthUnits {
+    /// Millimetres
+    Mm,
+    /// Metres
+    M,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DebayerMethod {
+    /// Average of the nearest pixels of the same color
+    Bilinear,
+    /// Hamilton-Adams interpolation along the edges with smaller gradient
+    EdgeAware,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum YuvMatrix {
+    /// ITU-R BT.601, standard definition video
+    Bt601,
+    /// ITU-R BT.709, high definition video
+    Bt709,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SplitChannels {
+    /// Every channel as a grayscale image
+    Gray,
+    /// Every three channels as a false color RGB image, the rest as grayscale images
+    Rgb,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum FlowFormat {
+    /// 8-bit RGB PNG with direction as hue and magnitude as brightness
+    Png,
+    /// Middlebury `.flo` file with raw flow vectors
+    Flo,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Palette {
+    /// Black, purple, red, yellow and white, like most thermal cameras
+    Ironbow,
+    /// Blue, cyan, green, yellow and red
+    Rainbow,
+    /// Black and white
+    Grayscale,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Lossless PNG, keeps 16-bit and transparent images as is
+    Png,
+    /// Lossy JPEG, 16-bit images are reduced to 8 bits and transparency is dropped
+    Jpeg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum VideoFormat {
+    /// MP4 container with the timestamps of the messages
+    Mp4,
+    /// Raw H.264/H.265 elementary stream with start codes (`.h264` or `.h265`)
+    AnnexB,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LabelFormat {
+    /// YOLO `.txt` label files next to the images, with `classes.txt` and `data.yaml`
+    Yolo,
+    /// COCO `annotations.json` with all exported images and the paired detections
+    Coco,
 }
@@ -0,0 +1,32 @@
+use image::Rgb;
+
+/// Parse color specified as `#RRGGBB`, `RRGGBB` or `R,G,B`.
+///
+/// # Examples
+/// ```rust
+/// assert_eq!(parse_color("#ff8000"), Ok(Rgb([255, 128, 0])));
+/// assert_eq!(parse_color("255,128,0"), Ok(Rgb([255, 128, 0])));
+/// ```
+pub fn parse_color(value: &str) -> Result<Rgb<u8>, String> {
+    let invalid = || format!("`{}` is not a color, use `#RRGGBB` or `R,G,B`", value);
+    let value = value.trim();
+
+    if value.contains(',') {
+        let channels = value
+            .split(',')
+            .map(|c| c.trim().parse::<u8>())
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| invalid())?;
+        return match channels[..] {
+            [r, g, b] => Ok(Rgb([r, g, b])),
+            _ => Err(invalid()),
+        };
+    }
+
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(invalid());
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid());
+    Ok(Rgb([channel(0)?, channel(2)?, channel(4)?]))
+}
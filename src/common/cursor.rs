@@ -9,7 +9,9 @@ use super::error::AppError;
 /// The main differences from `io::Cursor`:
 /// * you can only read data from an `&[u8]`
 /// * you have convenient functions for reading directly
-/// into `u8`, `u32`, unix timestamp, `&[u8; N]`.
+///   into `u32` and `&[u8; N]`.
+///
+/// Messages are read with [super::de::Deserializer] built on top of the `Cursor`.
 pub struct Cursor<'a> {
     data: &'a [u8],
     pos: u64,
@@ -24,13 +26,18 @@ impl<'a> Cursor<'a> {
         self.data.len() as u64
     }
 
+    /// Get all bytes which are not read yet
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.data[self.pos as usize..]
+    }
+
     /// Get next `n` bytes or [AppError::OutOfBounds]
     ///
     /// # Arguments
     ///
     /// * `n` - number of bytes to read
     pub fn next_bytes(&mut self, n: u64) -> Result<&'a [u8], AppError> {
-        if self.pos + n > self.len() {
+        if n > self.len() - self.pos {
             return Err(AppError::OutOfBounds);
         }
         let s = self.pos as usize;
@@ -47,20 +54,8 @@ impl<'a> Cursor<'a> {
         self.next_bytes(n)
     }
 
-    /// Get next byte as `u8` integer
-    pub fn next_u8(&mut self) -> Result<u8, AppError> {
-        Ok(self.next_bytes(1)?[0])
-    }
-
     /// Get next 32 bits as `u32` integer
     pub fn next_u32(&mut self) -> Result<u32, AppError> {
         Ok(LE::read_u32(self.next_bytes(4)?))
     }
-
-    /// Get next 64 bits as UNIX time
-    pub fn next_time(&mut self) -> Result<u64, AppError> {
-        let s = self.next_u32()? as u64;
-        let ns = self.next_u32()? as u64;
-        Ok(1_000_000_000 * s + ns)
-    }
 }
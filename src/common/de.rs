@@ -0,0 +1,354 @@
+use byteorder::{ByteOrder, LE};
+use serde::de::{self, DeserializeSeed, SeqAccess, Visitor};
+use serde::Deserialize;
+
+use super::cursor::Cursor;
+use super::error::AppError;
+
+/// Deserializer of the ROS1 serialization format.
+///
+/// Messages are declared as plain structs with `#[derive(Deserialize)]`,
+/// fields must go in the same order as in the message definition.
+/// Types are mapped as follows:
+/// * `bool`, integers and floats are little-endian primitives
+/// * `&str` and `String` are strings prefixed with the `u32` length
+/// * `&[u8]` is the `uint8[]` array prefixed with the `u32` length, borrowed without copying
+/// * `Vec<T>` is the variable-length array prefixed with the `u32` number of elements
+/// * `[T; N]` and tuples are fixed-length arrays without prefix
+/// * structs are nested messages serialized in place, e.g. [Time] and [Duration]
+///
+/// The format is not self-describing, so `Option`, enums and maps are not supported.
+///
+/// Specification: http://wiki.ros.org/msg#Serialization_format
+pub struct Deserializer<'de, 'a> {
+    cursor: &'a mut Cursor<'de>,
+}
+
+/// ROS `time` primitive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+pub struct Time {
+    pub sec: u32,
+    pub nsec: u32,
+}
+
+/// ROS `duration` primitive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+pub struct Duration {
+    pub sec: i32,
+    pub nsec: i32,
+}
+
+impl Time {
+    /// Nanoseconds since the UNIX epoch
+    pub fn nanos(&self) -> u64 {
+        self.sec as u64 * 1_000_000_000 + self.nsec as u64
+    }
+}
+
+/// Deserialize the message from the current position of the cursor.
+/// Bytes after the message are left unread.
+pub fn from_cursor<'de, T>(cursor: &mut Cursor<'de>) -> Result<T, AppError>
+where
+    T: Deserialize<'de>,
+{
+    T::deserialize(&mut Deserializer { cursor })
+}
+
+/// Deserialize the message from the beginning of the bytes
+pub fn from_bytes<'de, T>(data: &'de [u8]) -> Result<T, AppError>
+where
+    T: Deserialize<'de>,
+{
+    from_cursor(&mut Cursor::new(data))
+}
+
+impl de::Error for AppError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        AppError::InvalidMessage(msg.to_string())
+    }
+}
+
+impl<'de, 'a> Deserializer<'de, 'a> {
+    fn next_bytes(&mut self, n: usize) -> Result<&'de [u8], AppError> {
+        self.cursor.next_bytes(n as u64)
+    }
+
+    fn next_str(&mut self) -> Result<&'de str, AppError> {
+        std::str::from_utf8(self.cursor.next_chunk()?).map_err(|_| AppError::InvalidUtf8String)
+    }
+
+    fn unsupported(what: &str) -> AppError {
+        AppError::InvalidMessage(format!("{} can't be read from ROS messages", what))
+    }
+}
+
+macro_rules! deserialize_primitive {
+    ($method:ident, $visit:ident, $size:expr, $read:expr) => {
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, AppError> {
+            let bytes = self.next_bytes($size)?;
+            visitor.$visit($read(bytes))
+        }
+    };
+}
+
+macro_rules! deserialize_unsupported {
+    ($($method:ident => $what:expr),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, AppError> {
+                Err(Deserializer::unsupported($what))
+            }
+        )*
+    };
+}
+
+impl<'de, 'a, 'b> de::Deserializer<'de> for &'b mut Deserializer<'de, 'a> {
+    type Error = AppError;
+
+    deserialize_primitive!(deserialize_bool, visit_bool, 1, |b: &[u8]| b[0] != 0);
+    deserialize_primitive!(deserialize_i8, visit_i8, 1, |b: &[u8]| b[0] as i8);
+    deserialize_primitive!(deserialize_u8, visit_u8, 1, |b: &[u8]| b[0]);
+    deserialize_primitive!(deserialize_i16, visit_i16, 2, LE::read_i16);
+    deserialize_primitive!(deserialize_u16, visit_u16, 2, LE::read_u16);
+    deserialize_primitive!(deserialize_i32, visit_i32, 4, LE::read_i32);
+    deserialize_primitive!(deserialize_u32, visit_u32, 4, LE::read_u32);
+    deserialize_primitive!(deserialize_i64, visit_i64, 8, LE::read_i64);
+    deserialize_primitive!(deserialize_u64, visit_u64, 8, LE::read_u64);
+    deserialize_primitive!(deserialize_f32, visit_f32, 4, LE::read_f32);
+    deserialize_primitive!(deserialize_f64, visit_f64, 8, LE::read_f64);
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, AppError> {
+        visitor.visit_borrowed_str(self.next_str()?)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, AppError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, AppError> {
+        visitor.visit_borrowed_bytes(self.cursor.next_chunk()?)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, AppError> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, AppError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, AppError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, AppError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, AppError> {
+        let len = self.cursor.next_u32()? as usize;
+        visitor.visit_seq(Elements { de: self, len })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, AppError> {
+        visitor.visit_seq(Elements { de: self, len })
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, AppError> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, AppError> {
+        self.deserialize_tuple(fields.len(), visitor)
+    }
+
+    deserialize_unsupported! {
+        deserialize_any => "values of unknown type",
+        deserialize_char => "`char` values (use `u8` for ROS `char`)",
+        deserialize_option => "optional values",
+        deserialize_map => "maps",
+        deserialize_identifier => "identifiers",
+        deserialize_ignored_any => "ignored values",
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, AppError> {
+        Err(Deserializer::unsupported("enums"))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+/// Elements of arrays and fields of structs, which are serialized one after another
+struct Elements<'b, 'de, 'a> {
+    de: &'b mut Deserializer<'de, 'a>,
+    len: usize,
+}
+
+impl<'b, 'de, 'a> SeqAccess<'de> for Elements<'b, 'de, 'a> {
+    type Error = AppError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, AppError> {
+        if self.len == 0 {
+            return Ok(None);
+        }
+        self.len -= 1;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Nested {
+        id: u16,
+        stamp: Time,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Message<'a> {
+        flag: bool,
+        small: i8,
+        count: u32,
+        offset: i64,
+        scale: f32,
+        value: f64,
+        name: &'a str,
+        data: &'a [u8],
+        owned: String,
+        pose: [f64; 2],
+        pair: (u8, i16),
+        nested: Nested,
+        items: Vec<Nested>,
+        delay: Duration,
+    }
+
+    fn serialized() -> Vec<u8> {
+        [
+            vec![1, 0xfe],
+            7u32.to_le_bytes().to_vec(),
+            (-5i64).to_le_bytes().to_vec(),
+            0.5f32.to_le_bytes().to_vec(),
+            1.25f64.to_le_bytes().to_vec(),
+            vec![3, 0, 0, 0, b'c', b'a', b'm'],
+            vec![2, 0, 0, 0, 9, 8],
+            vec![1, 0, 0, 0, b'x'],
+            [1f64.to_le_bytes(), 2f64.to_le_bytes()].concat(),
+            vec![4, 0xff, 0xff],
+            vec![5, 0, 1, 0, 0, 0, 2, 0, 0, 0],
+            vec![1, 0, 0, 0, 6, 0, 3, 0, 0, 0, 4, 0, 0, 0],
+            (-1i32).to_le_bytes().to_vec(),
+            10i32.to_le_bytes().to_vec(),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn deserialize_message() {
+        let data = serialized();
+        let message = from_bytes::<Message>(&data).unwrap();
+        assert_eq!(
+            message,
+            Message {
+                flag: true,
+                small: -2,
+                count: 7,
+                offset: -5,
+                scale: 0.5,
+                value: 1.25,
+                name: "cam",
+                data: &[9, 8],
+                owned: "x".to_string(),
+                pose: [1.0, 2.0],
+                pair: (4, -1),
+                nested: Nested {
+                    id: 5,
+                    stamp: Time { sec: 1, nsec: 2 },
+                },
+                items: vec![Nested {
+                    id: 6,
+                    stamp: Time { sec: 3, nsec: 4 },
+                }],
+                delay: Duration { sec: -1, nsec: 10 },
+            }
+        );
+        assert_eq!(message.nested.stamp.nanos(), 1_000_000_002);
+    }
+
+    #[test]
+    fn leave_bytes_after_message() {
+        let data = [5, 0, 1, 0, 0, 0, 2, 0, 0, 0, 0xaa];
+        let mut cursor = Cursor::new(&data);
+        let nested = from_cursor::<Nested>(&mut cursor).unwrap();
+        assert_eq!(nested.id, 5);
+        assert_eq!(cursor.remaining(), [0xaa]);
+    }
+
+    #[test]
+    fn deserialize_truncated_message() {
+        let data = serialized();
+        for len in [0, 1, 10, data.len() - 1] {
+            assert!(matches!(
+                from_bytes::<Message>(&data[..len]),
+                Err(AppError::OutOfBounds)
+            ));
+        }
+        // string length is larger than the message
+        assert!(matches!(
+            from_bytes::<&str>(&[9, 0, 0, 0, b'a']),
+            Err(AppError::OutOfBounds)
+        ));
+    }
+
+    #[test]
+    fn deserialize_invalid_values() {
+        assert!(matches!(
+            from_bytes::<&str>(&[2, 0, 0, 0, 0xff, 0xfe]),
+            Err(AppError::InvalidUtf8String)
+        ));
+        assert!(matches!(
+            from_bytes::<Option<u8>>(&[1, 0]),
+            Err(AppError::InvalidMessage(_))
+        ));
+        assert!(matches!(
+            from_bytes::<char>(b"a"),
+            Err(AppError::InvalidMessage(_))
+        ));
+    }
+}
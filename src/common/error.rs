@@ -13,8 +13,34 @@ pub enum AppError {
     OutOfBounds,
     #[error("Invalid UTF-8 string encountered when reading byte stream")]
     InvalidUtf8String,
+    #[error("Cannot deserialize message from byte stream. Cause: {0}")]
+    InvalidMessage(String),
     #[error("Cannot decode frame with encoding {0}")]
     InvalidImageEncoding(String),
+    #[error("Encoding `{0}` is not supported. Try running `bagimages --list-encodings`")]
+    UnsupportedImageEncoding(String),
+    #[error("Frames with encoding `{0}` have too many channels to be saved as a single image. Try `--split-channels gray`")]
+    TooManyChannels(String),
+    #[error("Cannot decode compressed image with format `{0}`. Cause: {1}")]
+    InvalidCompressedImage(String, String),
+    #[error("Cannot parse definition of message `{0}`. Cause: {1}")]
+    InvalidMessageDefinition(String, String),
+    #[error("Cannot find field `{0}` in message. Cause: {1}")]
+    InvalidFieldPath(String, String),
+    #[error("Cannot decode point cloud. Cause: {0}")]
+    InvalidPointCloud(String),
+    #[error("Point cloud has no field `{0}`, available fields: `{1}`")]
+    UnknownCloudField(String, String),
+    #[error("Video format `{0}` is not supported, supported formats: `h264`, `h265`")]
+    UnsupportedVideoFormat(String),
+    #[error("Cannot extract video stream to `{0}`. Cause: {1}")]
+    InvalidVideoStream(String, String),
+    #[error("Cannot read palette file `{0}`. Cause: {1}")]
+    InvalidPalette(String, String),
+    #[error("Topic `{0}` has no segmentation masks, class ids must be in `mono8`, `8UC1`, `mono16` or `16UC1` images")]
+    NotLabelMap(String),
+    #[error("Cannot blend segmentation masks onto topic `{0}`, it has no color images")]
+    OverlayNotImage(String),
     #[error("Cannot save file as `{0}`. Cause: {1}")]
     CannotSave(String, String),
 
@@ -22,7 +48,7 @@ pub enum AppError {
     ArgsNegativeTime(f64, f64),
     #[error("End time is less than start time (you specified start={0}, end={1})")]
     ArgsEndBeforeStart(f64, f64),
-    #[error("Step value cannot be {0} (you specified --step {0} or -S{})")]
+    #[error("Step value cannot be {0} (you specified --step {0} or -S{0})")]
     ArgsNegativeStep(u32),
     #[error("Number of frames to export cannot be {0} (you specified --number {0} or -n{0})")]
     ArgsNegativeNumber(u32),
@@ -30,4 +56,14 @@ pub enum AppError {
     ArgsEmptyTopics,
     #[error("String `{0}` is not a valid regular expression")]
     ArgsInvalidRegex(String),
+    #[error(
+        "Depth in 16-bit PNG is always exported in millimetres (you specified --depth-units m)"
+    )]
+    ArgsDepthUnits,
+    #[error("Maximum flow magnitude must be positive (you specified --flow-max {0})")]
+    ArgsFlowMax(f32),
+    #[error("Labels are made from detections, specify them with --detections IMAGE_TOPIC=DETECTIONS_TOPIC")]
+    ArgsLabelsWithoutDetections,
+    #[error("Opacity of segmentation masks must be from 0 to 1 (you specified --seg-alpha {0})")]
+    ArgsSegAlpha(f32),
 }
@@ -0,0 +1,28 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use byteorder::{WriteBytesExt, LE};
+
+/// Sanity check tag of `.flo` files, `PIEH` in ASCII
+const FLO_TAG: f32 = 202021.25;
+
+/// Write optical flow vectors to the file in Middlebury `.flo` format.
+///
+/// It is the de facto standard format of optical flow datasets,
+/// it can be read with OpenCV (`cv2.readOpticalFlow(path)`) and most flow toolkits.
+///
+/// # Arguments
+///
+/// * `path` - path to the output file
+/// * `width`, `height` - flow field size
+/// * `data` - row-major interleaved `u` and `v` components starting from the top-left corner
+pub fn write_flo(path: &str, width: u32, height: u32, data: &[f32]) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_f32::<LE>(FLO_TAG)?;
+    writer.write_i32::<LE>(width as i32)?;
+    writer.write_i32::<LE>(height as i32)?;
+    for value in data {
+        writer.write_f32::<LE>(*value)?;
+    }
+    writer.flush()
+}
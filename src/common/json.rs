@@ -0,0 +1,38 @@
+use std::fmt::Write;
+
+/// Quote the string as a JSON string literal, escaping quotes, backslashes
+/// and control characters.
+///
+/// # Examples
+/// ```rust
+/// assert_eq!(quote("say \"hi\"\n"), "\"say \\\"hi\\\"\\n\"");
+/// ```
+pub fn quote(value: &str) -> String {
+    let mut output = String::with_capacity(value.len() + 2);
+    output.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => {
+                write!(output, "\\u{:04x}", ch as u32).expect("writing to string doesn't fail")
+            }
+            ch => output.push(ch),
+        }
+    }
+    output.push('"');
+    output
+}
+
+/// Format the number for JSON, which has no NaN and infinity.
+/// Integral values are written without the fractional part.
+pub fn number(value: f64) -> String {
+    if value.is_finite() {
+        format!("{}", value)
+    } else {
+        "null".to_string()
+    }
+}
@@ -1,3 +1,13 @@
+pub mod color;
 pub mod cursor;
+pub mod de;
 pub mod error;
+pub mod flo;
+pub mod json;
+pub mod mp4;
+pub mod msg_def;
 pub mod naming;
+pub mod pfm;
+pub mod range;
+pub mod size;
+pub mod topic_pair;
@@ -0,0 +1,529 @@
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+
+use byteorder::{WriteBytesExt, BE};
+
+/// Time units per second of the video track, the usual one for video
+const TIMESCALE: u32 = 90_000;
+
+/// Time units per second of the movie
+const MOVIE_TIMESCALE: u32 = 1_000;
+
+/// Duration of the last sample if it can't be guessed from the previous ones, 1/30 sec
+const DEFAULT_DURATION: u32 = TIMESCALE / 30;
+
+/// Size of the `mdat` box header with the 64-bit size
+const MDAT_HEADER_SIZE: u64 = 16;
+
+/// Identity transformation matrix of the movie and tracks
+const MATRIX: [u32; 9] = [0x10000, 0, 0, 0, 0x10000, 0, 0, 0, 0x4000_0000];
+
+/// Writer of MP4 files with a single video track.
+///
+/// Samples are written to the `mdat` box as they come, sample tables
+/// are written to the `moov` box at the end of the file by [Mp4Writer::finish].
+///
+/// Specification: ISO/IEC 14496-12 (ISO base media file format)
+pub struct Mp4Writer {
+    writer: BufWriter<File>,
+    /// Position of the `mdat` box in the file
+    mdat_start: u64,
+    /// Size of the written samples
+    mdat_size: u64,
+    samples: Vec<Sample>,
+}
+
+#[derive(Debug)]
+struct Sample {
+    size: u32,
+    /// Timestamp in nanoseconds
+    time: u64,
+    /// Is this sample a keyframe?
+    sync: bool,
+}
+
+/// Timing of the samples in the track timescale
+struct Timing {
+    /// Decoding durations
+    durations: Vec<u32>,
+    /// Composition offsets, presentation times are decoding times plus offsets
+    offsets: Vec<u32>,
+    /// Presentation time of the first frame
+    delay: u32,
+}
+
+/// Video sample description, the `stsd` entry of the track
+#[derive(Debug)]
+pub struct SampleEntry {
+    /// Sample entry type, e.g. `avc1`
+    pub kind: [u8; 4],
+    pub width: u32,
+    pub height: u32,
+    /// Codec configuration box, e.g. `avcC`
+    pub config: Vec<u8>,
+}
+
+impl std::fmt::Debug for Mp4Writer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Mp4Writer")
+            .field("mdat_size", &self.mdat_size)
+            .field("samples", &self.samples.len())
+            .finish()
+    }
+}
+
+impl Mp4Writer {
+    /// Create the file and write the header
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - path to the output file
+    /// * `brand` - compatible brand of the codec, e.g. `avc1`
+    pub fn create(path: &str, brand: [u8; 4]) -> std::io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        let mut ftyp = Vec::new();
+        ftyp.write_all(b"isom")?;
+        ftyp.write_u32::<BE>(0x200)?;
+        for compatible in [*b"isom", *b"iso2", brand, *b"mp41"] {
+            ftyp.write_all(&compatible)?;
+        }
+        let ftyp = mp4_box(b"ftyp", &ftyp);
+        writer.write_all(&ftyp)?;
+
+        // size is unknown until all samples are written
+        writer.write_u32::<BE>(1)?;
+        writer.write_all(b"mdat")?;
+        writer.write_u64::<BE>(MDAT_HEADER_SIZE)?;
+
+        Ok(Self {
+            writer,
+            mdat_start: ftyp.len() as u64,
+            mdat_size: 0,
+            samples: Vec::new(),
+        })
+    }
+
+    /// Append the sample to the file
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - sample data in the format of the codec configuration
+    /// * `time` - presentation timestamp in nanoseconds, samples are written
+    ///   in the decoding order, so timestamps decrease at B-frames
+    /// * `sync` - is this sample a keyframe?
+    pub fn write_sample(&mut self, data: &[u8], time: u64, sync: bool) -> std::io::Result<()> {
+        self.writer.write_all(data)?;
+        self.mdat_size += data.len() as u64;
+        self.samples.push(Sample {
+            size: data.len() as u32,
+            time,
+            sync,
+        });
+        Ok(())
+    }
+
+    /// Write the sample tables and close the file
+    pub fn finish(mut self, entry: &SampleEntry) -> std::io::Result<()> {
+        let moov = self.moov(entry, self.mdat_start + MDAT_HEADER_SIZE)?;
+        self.writer.write_all(&moov)?;
+
+        // 64-bit size goes after the 32-bit size and the type
+        self.writer.seek(SeekFrom::Start(self.mdat_start + 8))?;
+        self.writer
+            .write_u64::<BE>(MDAT_HEADER_SIZE + self.mdat_size)?;
+        self.writer.flush()
+    }
+
+    /// Timing of the samples in the track timescale.
+    ///
+    /// Sorted timestamps are used as decoding times, so streams with B-frames
+    /// get composition offsets, and their decoding is delayed to keep the offsets positive.
+    fn timing(&self) -> Timing {
+        let start = self
+            .samples
+            .iter()
+            .map(|s| s.time)
+            .min()
+            .unwrap_or_default();
+        let times = self
+            .samples
+            .iter()
+            .map(|s| (s.time - start) as u128 * TIMESCALE as u128 / 1_000_000_000)
+            .collect::<Vec<u128>>();
+        let mut sorted = times.clone();
+        sorted.sort_unstable();
+        let mut durations = sorted
+            .windows(2)
+            .map(|w| (w[1] - w[0]).clamp(1, u32::MAX as u128) as u32)
+            .collect::<Vec<u32>>();
+        durations.push(durations.last().copied().unwrap_or(DEFAULT_DURATION));
+
+        let decode_times = durations
+            .iter()
+            .scan(0i128, |time, duration| {
+                let start = *time;
+                *time += *duration as i128;
+                Some(start)
+            })
+            .collect::<Vec<i128>>();
+        let delay = decode_times
+            .iter()
+            .zip(&times)
+            .map(|(decode, time)| decode - *time as i128)
+            .max()
+            .unwrap_or_default()
+            .max(0);
+        let offsets = decode_times
+            .iter()
+            .zip(&times)
+            .map(|(decode, time)| {
+                (*time as i128 + delay - decode).clamp(0, u32::MAX as i128) as u32
+            })
+            .collect();
+        Timing {
+            durations,
+            offsets,
+            delay: delay.min(i32::MAX as i128) as u32,
+        }
+    }
+
+    fn moov(&self, entry: &SampleEntry, chunk_offset: u64) -> std::io::Result<Vec<u8>> {
+        let Timing {
+            durations,
+            offsets,
+            delay,
+        } = self.timing();
+        let duration = durations.iter().map(|d| *d as u64).sum::<u64>();
+        let movie_duration = duration * MOVIE_TIMESCALE as u64 / TIMESCALE as u64;
+
+        let mut mvhd = Vec::new();
+        mvhd.write_u32::<BE>(0)?; // creation time
+        mvhd.write_u32::<BE>(0)?; // modification time
+        mvhd.write_u32::<BE>(MOVIE_TIMESCALE)?;
+        mvhd.write_u32::<BE>(movie_duration as u32)?;
+        mvhd.write_u32::<BE>(0x10000)?; // rate 1.0
+        mvhd.write_u16::<BE>(0x100)?; // volume 1.0
+        mvhd.write_all(&[0; 10])?;
+        for value in MATRIX {
+            mvhd.write_u32::<BE>(value)?;
+        }
+        mvhd.write_all(&[0; 24])?;
+        mvhd.write_u32::<BE>(2)?; // next track id
+
+        let mut tkhd = Vec::new();
+        tkhd.write_u32::<BE>(0)?; // creation time
+        tkhd.write_u32::<BE>(0)?; // modification time
+        tkhd.write_u32::<BE>(1)?; // track id
+        tkhd.write_u32::<BE>(0)?;
+        tkhd.write_u32::<BE>(movie_duration as u32)?;
+        tkhd.write_all(&[0; 8])?;
+        tkhd.write_u16::<BE>(0)?; // layer
+        tkhd.write_u16::<BE>(0)?; // alternate group
+        tkhd.write_u16::<BE>(0)?; // volume
+        tkhd.write_u16::<BE>(0)?;
+        for value in MATRIX {
+            tkhd.write_u32::<BE>(value)?;
+        }
+        tkhd.write_u32::<BE>(entry.width << 16)?;
+        tkhd.write_u32::<BE>(entry.height << 16)?;
+
+        let mut mdhd = Vec::new();
+        mdhd.write_u32::<BE>(0)?; // creation time
+        mdhd.write_u32::<BE>(0)?; // modification time
+        mdhd.write_u32::<BE>(TIMESCALE)?;
+        mdhd.write_u32::<BE>(duration.min(u32::MAX as u64) as u32)?;
+        mdhd.write_u16::<BE>(0x55c4)?; // `und` language
+        mdhd.write_u16::<BE>(0)?;
+
+        let mut hdlr = Vec::new();
+        hdlr.write_u32::<BE>(0)?;
+        hdlr.write_all(b"vide")?;
+        hdlr.write_all(&[0; 12])?;
+        hdlr.write_all(b"VideoHandler\0")?;
+
+        let mut vmhd = Vec::new();
+        vmhd.write_all(&[0; 8])?; // graphics mode and color
+
+        let mut dref = Vec::new();
+        dref.write_u32::<BE>(1)?;
+        dref.write_all(&full_box(b"url ", 0, 1, &[]))?; // data is in the same file
+
+        let mut stbl = [
+            full_box(b"stsd", 0, 0, &self.stsd(entry)?),
+            full_box(b"stts", 0, 0, &run_lengths(&durations)?),
+        ]
+        .concat();
+        // samples are presented in the decoding order without B-frames
+        if offsets.iter().any(|offset| *offset != 0) {
+            stbl.extend(full_box(b"ctts", 0, 0, &run_lengths(&offsets)?));
+        }
+        stbl.extend(
+            [
+                full_box(b"stss", 0, 0, &self.stss()?),
+                full_box(b"stsc", 0, 0, &self.stsc()?),
+                full_box(b"stsz", 0, 0, &self.stsz()?),
+                full_box(b"co64", 0, 0, &co64(chunk_offset)?),
+            ]
+            .concat(),
+        );
+
+        let minf = [
+            full_box(b"vmhd", 0, 1, &vmhd),
+            mp4_box(b"dinf", &full_box(b"dref", 0, 0, &dref)),
+            mp4_box(b"stbl", &stbl),
+        ]
+        .concat();
+
+        let mdia = [
+            full_box(b"mdhd", 0, 0, &mdhd),
+            full_box(b"hdlr", 0, 0, &hdlr),
+            mp4_box(b"minf", &minf),
+        ]
+        .concat();
+
+        // track is enabled and used in the presentation
+        let mut trak = full_box(b"tkhd", 0, 3, &tkhd);
+        // presentation starts from the first frame, not from the decoding delay
+        if delay > 0 {
+            let mut elst = Vec::new();
+            elst.write_u32::<BE>(1)?;
+            elst.write_u32::<BE>(movie_duration as u32)?;
+            elst.write_u32::<BE>(delay)?; // media time
+            elst.write_u32::<BE>(0x10000)?; // rate 1.0
+            trak.extend(mp4_box(b"edts", &full_box(b"elst", 0, 0, &elst)));
+        }
+        trak.extend(mp4_box(b"mdia", &mdia));
+
+        let moov = [full_box(b"mvhd", 0, 0, &mvhd), mp4_box(b"trak", &trak)].concat();
+        Ok(mp4_box(b"moov", &moov))
+    }
+
+    fn stsd(&self, entry: &SampleEntry) -> std::io::Result<Vec<u8>> {
+        let mut visual = Vec::new();
+        visual.write_all(&[0; 6])?;
+        visual.write_u16::<BE>(1)?; // data reference index
+        visual.write_all(&[0; 16])?;
+        visual.write_u16::<BE>(entry.width as u16)?;
+        visual.write_u16::<BE>(entry.height as u16)?;
+        visual.write_u32::<BE>(0x480000)?; // 72 dpi
+        visual.write_u32::<BE>(0x480000)?;
+        visual.write_u32::<BE>(0)?;
+        visual.write_u16::<BE>(1)?; // frame count
+        visual.write_all(&[0; 32])?; // compressor name
+        visual.write_u16::<BE>(0x18)?; // depth
+        visual.write_i16::<BE>(-1)?;
+        visual.write_all(&entry.config)?;
+
+        let mut stsd = Vec::new();
+        stsd.write_u32::<BE>(1)?;
+        stsd.write_all(&mp4_box(&entry.kind, &visual))?;
+        Ok(stsd)
+    }
+
+    fn stss(&self) -> std::io::Result<Vec<u8>> {
+        let sync = self
+            .samples
+            .iter()
+            .enumerate()
+            .filter(|(_, sample)| sample.sync)
+            .map(|(i, _)| i as u32 + 1)
+            .collect::<Vec<u32>>();
+        let mut stss = Vec::new();
+        stss.write_u32::<BE>(sync.len() as u32)?;
+        for number in sync {
+            stss.write_u32::<BE>(number)?;
+        }
+        Ok(stss)
+    }
+
+    /// All samples are in a single chunk
+    fn stsc(&self) -> std::io::Result<Vec<u8>> {
+        let mut stsc = Vec::new();
+        stsc.write_u32::<BE>(1)?;
+        stsc.write_u32::<BE>(1)?; // first chunk
+        stsc.write_u32::<BE>(self.samples.len() as u32)?;
+        stsc.write_u32::<BE>(1)?; // sample description index
+        Ok(stsc)
+    }
+
+    fn stsz(&self) -> std::io::Result<Vec<u8>> {
+        let mut stsz = Vec::new();
+        stsz.write_u32::<BE>(0)?; // samples have different sizes
+        stsz.write_u32::<BE>(self.samples.len() as u32)?;
+        for sample in &self.samples {
+            stsz.write_u32::<BE>(sample.size)?;
+        }
+        Ok(stsz)
+    }
+}
+
+/// Run-length encoded values of the samples, the content of `stts` and `ctts`
+fn run_lengths(values: &[u32]) -> std::io::Result<Vec<u8>> {
+    let mut runs: Vec<(u32, u32)> = Vec::new();
+    for value in values {
+        match runs.last_mut() {
+            Some((count, last)) if last == value => *count += 1,
+            _ => runs.push((1, *value)),
+        }
+    }
+    let mut data = Vec::new();
+    data.write_u32::<BE>(runs.len() as u32)?;
+    for (count, value) in runs {
+        data.write_u32::<BE>(count)?;
+        data.write_u32::<BE>(value)?;
+    }
+    Ok(data)
+}
+
+fn co64(offset: u64) -> std::io::Result<Vec<u8>> {
+    let mut co64 = Vec::new();
+    co64.write_u32::<BE>(1)?;
+    co64.write_u64::<BE>(offset)?;
+    Ok(co64)
+}
+
+/// Box with the size and the type
+pub fn mp4_box(kind: &[u8; 4], content: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(content.len() + 8);
+    data.extend_from_slice(&(content.len() as u32 + 8).to_be_bytes());
+    data.extend_from_slice(kind);
+    data.extend_from_slice(content);
+    data
+}
+
+/// Box with the version and flags
+pub fn full_box(kind: &[u8; 4], version: u8, flags: u32, content: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(content.len() + 4);
+    data.extend_from_slice(&((version as u32) << 24 | flags).to_be_bytes());
+    data.extend_from_slice(content);
+    mp4_box(kind, &data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Type and size of the boxes at the top level of `data`
+    fn boxes(data: &[u8]) -> Vec<(String, u64)> {
+        let mut boxes = Vec::new();
+        let mut pos = 0;
+        while pos + 8 <= data.len() {
+            let mut size = u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap()) as u64;
+            if size == 1 {
+                size = u64::from_be_bytes(data[pos + 8..pos + 16].try_into().unwrap());
+            }
+            boxes.push((
+                String::from_utf8_lossy(&data[pos + 4..pos + 8]).into(),
+                size,
+            ));
+            pos += size as usize;
+        }
+        boxes
+    }
+
+    /// Content of the first box with the type, the boxes are searched recursively
+    fn find<'a>(data: &'a [u8], kind: &[u8; 4]) -> Option<&'a [u8]> {
+        let pos = data.windows(4).position(|window| window == kind)?;
+        let size = u32::from_be_bytes(data[pos - 4..pos].try_into().unwrap()) as usize;
+        Some(&data[pos + 4..pos - 4 + size])
+    }
+
+    #[test]
+    fn write_two_samples() {
+        let path = std::env::temp_dir().join("bagimages_mp4_two_samples.mp4");
+        let path = path.to_str().unwrap();
+        let mut writer = Mp4Writer::create(path, *b"avc1").unwrap();
+        writer.write_sample(&[1; 10], 1_000_000_000, true).unwrap();
+        writer.write_sample(&[2; 6], 1_100_000_000, false).unwrap();
+        writer
+            .finish(&SampleEntry {
+                kind: *b"avc1",
+                width: 64,
+                height: 48,
+                config: mp4_box(b"avcC", &[1, 2, 3]),
+            })
+            .unwrap();
+        let data = std::fs::read(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        let boxes = boxes(&data);
+        let kinds = boxes
+            .iter()
+            .map(|(kind, _)| kind.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(kinds, ["ftyp", "mdat", "moov"]);
+        assert_eq!(boxes[0].1, 32);
+        assert_eq!(boxes[1].1, MDAT_HEADER_SIZE + 16);
+        assert_eq!(
+            boxes.iter().map(|(_, size)| size).sum::<u64>(),
+            data.len() as u64
+        );
+
+        // version and flags, sample size, count and sizes
+        let stsz = find(&data, b"stsz").unwrap();
+        assert_eq!(
+            stsz,
+            [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 10, 0, 0, 0, 6]
+        );
+        // both samples last 0.1 sec
+        let stts = find(&data, b"stts").unwrap();
+        assert_eq!(stts[4..], [0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0x23, 0x28]);
+        let stss = find(&data, b"stss").unwrap();
+        assert_eq!(stss[4..], [0, 0, 0, 1, 0, 0, 0, 1]);
+        // samples start right after the `mdat` header
+        let co64 = find(&data, b"co64").unwrap();
+        assert_eq!(co64[8..], (32 + MDAT_HEADER_SIZE).to_be_bytes());
+        // samples are in the presentation order
+        assert!(find(&data, b"ctts").is_none());
+        assert!(find(&data, b"edts").is_none());
+    }
+
+    #[test]
+    fn write_reordered_samples() {
+        let path = std::env::temp_dir().join("bagimages_mp4_reordered_samples.mp4");
+        let path = path.to_str().unwrap();
+        let mut writer = Mp4Writer::create(path, *b"avc1").unwrap();
+        // I P B B, presented as I B B P
+        for (time, sync) in [(0, true), (300, false), (100, false), (200, false)] {
+            writer
+                .write_sample(&[1; 4], 1_000_000_000 + time * 1_000_000, sync)
+                .unwrap();
+        }
+        writer
+            .finish(&SampleEntry {
+                kind: *b"avc1",
+                width: 64,
+                height: 48,
+                config: mp4_box(b"avcC", &[1, 2, 3]),
+            })
+            .unwrap();
+        let data = std::fs::read(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        let stts = find(&data, b"stts").unwrap();
+        assert_eq!(stts[4..], [0, 0, 0, 1, 0, 0, 0, 4, 0, 0, 0x23, 0x28]);
+        // decoding is delayed by a frame, so P is presented after both B
+        let ctts = find(&data, b"ctts").unwrap();
+        let mut expected = vec![0, 0, 0, 3];
+        for (count, offset) in [(1u32, 9000u32), (1, 27000), (2, 0)] {
+            expected.extend(count.to_be_bytes());
+            expected.extend(offset.to_be_bytes());
+        }
+        assert_eq!(ctts[4..], expected);
+        // presentation starts at the first frame, the track lasts 0.4 sec
+        let elst = find(&data, b"elst").unwrap();
+        assert_eq!(elst[4..8], 1u32.to_be_bytes());
+        assert_eq!(elst[8..12], 400u32.to_be_bytes());
+        assert_eq!(elst[12..16], 9000u32.to_be_bytes());
+    }
+
+    #[test]
+    fn full_box_size() {
+        let data = full_box(b"test", 1, 3, &[0xaa]);
+        assert_eq!(
+            data,
+            [0, 0, 0, 13, b't', b'e', b's', b't', 1, 0, 0, 3, 0xaa]
+        );
+    }
+}
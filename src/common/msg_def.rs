@@ -0,0 +1,776 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use super::cursor::Cursor;
+use super::de::{from_cursor, Duration, Time};
+use super::error::AppError;
+
+/// ROS messages can't be recursive, but malformed definitions can be
+const MAX_DEPTH: usize = 16;
+
+/// Built-in field types of ROS messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Primitive {
+    Bool,
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Int64,
+    UInt64,
+    Float32,
+    Float64,
+    String,
+    Time,
+    Duration,
+}
+
+/// Type of the message field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldType {
+    Primitive(Primitive),
+    /// Full name of the nested message type, e.g. `sensor_msgs/Image`
+    Message(String),
+}
+
+/// Array of field values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Array {
+    /// `type[]`, prefixed with the `u32` number of elements
+    Dynamic,
+    /// `type[N]`
+    Fixed(usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct Field {
+    pub name: String,
+    pub tp: FieldType,
+    pub array: Option<Array>,
+}
+
+/// Field value of the message decoded with its definition
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value<'a> {
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    String(&'a str),
+    Time(Time),
+    Duration(Duration),
+    /// `uint8[]` and `int8[]` arrays, borrowed without copying
+    Bytes(&'a [u8]),
+    Array(Vec<Value<'a>>),
+    /// Fields of the nested message in the order of the definition
+    Message(Vec<(&'a str, Value<'a>)>),
+}
+
+/// Parsed `message_definition` of the rosbag connection.
+///
+/// It contains the definition of the connection type and definitions
+/// of all nested types, so any message of the connection can be walked
+/// without knowing its type at compile time.
+#[derive(Debug)]
+pub struct MessageDefinition {
+    /// Full name of the connection type
+    root: String,
+    /// Fields of every type by its full name
+    types: HashMap<String, Vec<Field>>,
+}
+
+/// Path to the nested field of the message, e.g. `left.image` or `cameras[1].image`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FieldPath(Vec<PathSegment>);
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct PathSegment {
+    name: String,
+    /// Index of the element for array fields
+    index: Option<usize>,
+}
+
+impl Primitive {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "bool" => Self::Bool,
+            // `byte` and `char` are deprecated aliases
+            "int8" | "byte" => Self::Int8,
+            "uint8" | "char" => Self::UInt8,
+            "int16" => Self::Int16,
+            "uint16" => Self::UInt16,
+            "int32" => Self::Int32,
+            "uint32" => Self::UInt32,
+            "int64" => Self::Int64,
+            "uint64" => Self::UInt64,
+            "float32" => Self::Float32,
+            "float64" => Self::Float64,
+            "string" => Self::String,
+            "time" => Self::Time,
+            "duration" => Self::Duration,
+            _ => return None,
+        })
+    }
+
+    /// Size of the serialized value in bytes, `None` for strings
+    fn size(self) -> Option<usize> {
+        match self {
+            Self::Bool | Self::Int8 | Self::UInt8 => Some(1),
+            Self::Int16 | Self::UInt16 => Some(2),
+            Self::Int32 | Self::UInt32 | Self::Float32 => Some(4),
+            Self::Int64 | Self::UInt64 | Self::Float64 | Self::Time | Self::Duration => Some(8),
+            Self::String => None,
+        }
+    }
+}
+
+impl MessageDefinition {
+    /// Parse the `message_definition` text of the connection.
+    ///
+    /// The text starts with the fields of the connection type, definitions of
+    /// nested types follow it, each one after the `===` separator and `MSG: type` line.
+    /// Constants and comments are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - connection type, e.g. `perception_msgs/FrameBundle`
+    /// * `text` - message definition of the connection
+    pub fn parse(root: &str, text: &str) -> Result<Self, AppError> {
+        let invalid = |cause: String| AppError::InvalidMessageDefinition(root.to_string(), cause);
+        let mut types = HashMap::new();
+        let mut current = root.to_string();
+        let mut fields = Vec::new();
+
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with("===") {
+                types.insert(std::mem::take(&mut current), std::mem::take(&mut fields));
+                continue;
+            }
+            if let Some(name) = line.strip_prefix("MSG:") {
+                current = name.trim().to_string();
+                continue;
+            }
+            // constants like `uint8 DEBUG=1` are not serialized
+            if line.contains('=') {
+                continue;
+            }
+
+            let (tp, name) = line
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| invalid(format!("invalid field `{}`", line)))?;
+            let (tp, array) = match tp.split_once('[') {
+                Some((tp, "]")) => (tp, Some(Array::Dynamic)),
+                Some((tp, size)) => {
+                    let size = size
+                        .strip_suffix(']')
+                        .and_then(|size| size.parse::<usize>().ok())
+                        .ok_or_else(|| invalid(format!("invalid array type `{}`", line)))?;
+                    (tp, Some(Array::Fixed(size)))
+                }
+                None => (tp, None),
+            };
+            let tp = match Primitive::from_name(tp) {
+                Some(primitive) => FieldType::Primitive(primitive),
+                None => FieldType::Message(full_type_name(tp, &current)),
+            };
+            fields.push(Field {
+                name: name.trim().to_string(),
+                tp,
+                array,
+            });
+        }
+        types.insert(current, fields);
+
+        Ok(Self {
+            root: root.to_string(),
+            types,
+        })
+    }
+
+    /// Full name of the message type of the nested field
+    pub fn field_type(&self, path: &FieldPath) -> Result<&str, AppError> {
+        let mut tp = self.root.as_str();
+        for segment in &path.0 {
+            let field = self.field(tp, segment, path)?;
+            tp = self.nested_type(field, path)?;
+        }
+        Ok(tp)
+    }
+
+    /// Serialized nested field of the message.
+    ///
+    /// Nested messages are serialized in place, so the returned slice starts
+    /// with the field and goes until the end of the message.
+    /// Returns `None` if the message has no element with the index from the path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - path to the nested field
+    /// * `data` - serialized message of the connection type
+    pub fn locate<'a>(
+        &self,
+        path: &FieldPath,
+        data: &'a [u8],
+    ) -> Result<Option<&'a [u8]>, AppError> {
+        let mut cursor = Cursor::new(data);
+        let mut tp = self.root.as_str();
+        for segment in &path.0 {
+            let fields = self.fields(tp)?;
+            let field = self.field(tp, segment, path)?;
+            for previous in fields.iter().take_while(|f| f.name != field.name) {
+                self.skip_field(previous, &mut cursor, 0)?;
+            }
+            if let (Some(array), Some(index)) = (field.array, segment.index) {
+                let len = match array {
+                    Array::Dynamic => cursor.next_u32()? as usize,
+                    Array::Fixed(len) => len,
+                };
+                if index >= len {
+                    return Ok(None);
+                }
+                for _ in 0..index {
+                    self.skip_value(&field.tp, &mut cursor, 0)?;
+                }
+            }
+            tp = self.nested_type(field, path)?;
+        }
+        Ok(Some(cursor.remaining()))
+    }
+
+    /// Decode the whole message, so fields can be read by name.
+    ///
+    /// It's useful for messages which layout differs between versions of the package.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - serialized message of the connection type
+    pub fn decode<'a>(&'a self, data: &'a [u8]) -> Result<Value<'a>, AppError> {
+        let mut cursor = Cursor::new(data);
+        self.decode_value(&FieldType::Message(self.root.clone()), &mut cursor, 0)
+    }
+
+    fn decode_field<'a>(
+        &'a self,
+        field: &'a Field,
+        cursor: &mut Cursor<'a>,
+        depth: usize,
+    ) -> Result<Value<'a>, AppError> {
+        let len = match field.array {
+            None => return self.decode_value(&field.tp, cursor, depth),
+            Some(Array::Dynamic) => cursor.next_u32()? as usize,
+            Some(Array::Fixed(len)) => len,
+        };
+        match field.tp {
+            FieldType::Primitive(Primitive::UInt8 | Primitive::Int8) => {
+                Ok(Value::Bytes(cursor.next_bytes(len as u64)?))
+            }
+            _ => (0..len)
+                .map(|_| self.decode_value(&field.tp, cursor, depth))
+                .collect::<Result<Vec<Value>, AppError>>()
+                .map(Value::Array),
+        }
+    }
+
+    fn decode_value<'a>(
+        &'a self,
+        tp: &FieldType,
+        cursor: &mut Cursor<'a>,
+        depth: usize,
+    ) -> Result<Value<'a>, AppError> {
+        let primitive = match tp {
+            FieldType::Primitive(primitive) => primitive,
+            FieldType::Message(tp) => {
+                return self
+                    .nested_fields(tp, depth)?
+                    .iter()
+                    .map(|field| {
+                        let value = self.decode_field(field, cursor, depth + 1)?;
+                        Ok((field.name.as_str(), value))
+                    })
+                    .collect::<Result<Vec<(&str, Value)>, AppError>>()
+                    .map(Value::Message)
+            }
+        };
+        Ok(match primitive {
+            Primitive::Bool => Value::Bool(from_cursor(cursor)?),
+            Primitive::Int8 => Value::Int(from_cursor::<i8>(cursor)? as i64),
+            Primitive::UInt8 => Value::UInt(from_cursor::<u8>(cursor)? as u64),
+            Primitive::Int16 => Value::Int(from_cursor::<i16>(cursor)? as i64),
+            Primitive::UInt16 => Value::UInt(from_cursor::<u16>(cursor)? as u64),
+            Primitive::Int32 => Value::Int(from_cursor::<i32>(cursor)? as i64),
+            Primitive::UInt32 => Value::UInt(from_cursor::<u32>(cursor)? as u64),
+            Primitive::Int64 => Value::Int(from_cursor(cursor)?),
+            Primitive::UInt64 => Value::UInt(from_cursor(cursor)?),
+            Primitive::Float32 => Value::Float(from_cursor::<f32>(cursor)? as f64),
+            Primitive::Float64 => Value::Float(from_cursor(cursor)?),
+            Primitive::String => Value::String(from_cursor(cursor)?),
+            Primitive::Time => Value::Time(from_cursor(cursor)?),
+            Primitive::Duration => Value::Duration(from_cursor(cursor)?),
+        })
+    }
+
+    /// Paths to all nested fields of the specified types.
+    /// For array fields only the first element is listed.
+    pub fn find_fields(&self, types: &[&str]) -> Vec<FieldPath> {
+        let mut found = Vec::new();
+        self.find_fields_internal(&self.root, types, &mut Vec::new(), &mut found);
+        found
+    }
+
+    fn find_fields_internal(
+        &self,
+        tp: &str,
+        types: &[&str],
+        prefix: &mut Vec<PathSegment>,
+        found: &mut Vec<FieldPath>,
+    ) {
+        if prefix.len() >= MAX_DEPTH {
+            return;
+        }
+        for field in self.types.get(tp).into_iter().flatten() {
+            if let FieldType::Message(nested) = &field.tp {
+                prefix.push(PathSegment {
+                    name: field.name.clone(),
+                    index: field.array.map(|_| 0),
+                });
+                if types.contains(&nested.as_str()) {
+                    found.push(FieldPath(prefix.clone()));
+                } else {
+                    self.find_fields_internal(nested, types, prefix, found);
+                }
+                prefix.pop();
+            }
+        }
+    }
+
+    fn fields(&self, tp: &str) -> Result<&[Field], AppError> {
+        self.types.get(tp).map(Vec::as_slice).ok_or_else(|| {
+            AppError::InvalidMessageDefinition(
+                self.root.clone(),
+                format!("missing definition of `{}`", tp),
+            )
+        })
+    }
+
+    /// Fields of the type nested `depth` levels deep into the root type
+    fn nested_fields(&self, tp: &str, depth: usize) -> Result<&[Field], AppError> {
+        if depth >= MAX_DEPTH {
+            return Err(AppError::InvalidMessageDefinition(
+                self.root.clone(),
+                format!("type `{}` is nested too deep or recursive", tp),
+            ));
+        }
+        self.fields(tp)
+    }
+
+    fn field(&self, tp: &str, segment: &PathSegment, path: &FieldPath) -> Result<&Field, AppError> {
+        let field = self
+            .fields(tp)?
+            .iter()
+            .find(|field| field.name == segment.name)
+            .ok_or_else(|| {
+                AppError::InvalidFieldPath(
+                    path.to_string(),
+                    format!("type `{}` has no field `{}`", tp, segment.name),
+                )
+            })?;
+        match (field.array, segment.index) {
+            (Some(_), None) => Err(AppError::InvalidFieldPath(
+                path.to_string(),
+                format!("field `{0}` is an array, use `{0}[0]`", field.name),
+            )),
+            (None, Some(_)) => Err(AppError::InvalidFieldPath(
+                path.to_string(),
+                format!("field `{}` is not an array", field.name),
+            )),
+            _ => Ok(field),
+        }
+    }
+
+    fn nested_type<'a>(&self, field: &'a Field, path: &FieldPath) -> Result<&'a str, AppError> {
+        match &field.tp {
+            FieldType::Message(tp) => Ok(tp),
+            FieldType::Primitive(_) => Err(AppError::InvalidFieldPath(
+                path.to_string(),
+                format!("field `{}` is not a message", field.name),
+            )),
+        }
+    }
+
+    fn skip_field(&self, field: &Field, cursor: &mut Cursor, depth: usize) -> Result<(), AppError> {
+        let len = match field.array {
+            None => return self.skip_value(&field.tp, cursor, depth),
+            Some(Array::Dynamic) => cursor.next_u32()? as usize,
+            Some(Array::Fixed(len)) => len,
+        };
+        // arrays like `uint8[] data` are skipped at once
+        match self.fixed_size(&field.tp, depth)? {
+            Some(size) => {
+                let size = size.checked_mul(len).ok_or(AppError::OutOfBounds)?;
+                cursor.next_bytes(size as u64)?;
+            }
+            None => {
+                for _ in 0..len {
+                    self.skip_value(&field.tp, cursor, depth)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn skip_value(
+        &self,
+        tp: &FieldType,
+        cursor: &mut Cursor,
+        depth: usize,
+    ) -> Result<(), AppError> {
+        match tp {
+            FieldType::Primitive(primitive) => match primitive.size() {
+                Some(size) => cursor.next_bytes(size as u64).map(|_| ()),
+                None => cursor.next_chunk().map(|_| ()),
+            },
+            FieldType::Message(tp) => {
+                for field in self.nested_fields(tp, depth)? {
+                    self.skip_field(field, cursor, depth + 1)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Size of the serialized value in bytes, `None` for values with variable size
+    fn fixed_size(&self, tp: &FieldType, depth: usize) -> Result<Option<usize>, AppError> {
+        let tp = match tp {
+            FieldType::Primitive(primitive) => return Ok(primitive.size()),
+            FieldType::Message(tp) => tp,
+        };
+        let mut sum = 0usize;
+        for field in self.nested_fields(tp, depth)? {
+            let size = match self.fixed_size(&field.tp, depth + 1)? {
+                Some(size) => size,
+                None => return Ok(None),
+            };
+            let size = match field.array {
+                None => Some(size),
+                Some(Array::Fixed(len)) => size.checked_mul(len),
+                Some(Array::Dynamic) => return Ok(None),
+            };
+            sum = size.and_then(|size| sum.checked_add(size)).ok_or_else(|| {
+                AppError::InvalidMessageDefinition(
+                    self.root.clone(),
+                    format!("array `{}` of `{}` is too large", field.name, tp),
+                )
+            })?;
+        }
+        Ok(Some(sum))
+    }
+}
+
+impl<'a> Value<'a> {
+    /// Value of the field of the message, `None` if it's not a message or has no such field
+    pub fn get(&self, name: &str) -> Option<&Value<'a>> {
+        match self {
+            Self::Message(fields) => fields
+                .iter()
+                .find(|(field, _)| *field == name)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// Value of the nested field specified as dot separated field names, e.g. `bbox.center.x`
+    pub fn get_path(&self, path: &str) -> Option<&Value<'a>> {
+        path.split('.')
+            .try_fold(self, |value, name| value.get(name))
+    }
+
+    /// Numeric value converted to float
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Self::Int(value) => Some(value as f64),
+            Self::UInt(value) => Some(value as f64),
+            Self::Float(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value<'a>]> {
+        match self {
+            Self::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn as_time(&self) -> Option<Time> {
+        match self {
+            Self::Time(time) => Some(*time),
+            _ => None,
+        }
+    }
+}
+
+/// Resolve the type name used in the definition of the `current` type.
+///
+/// `Header` is a special case for `std_msgs/Header`, types without package
+/// are from the package of the `current` type.
+fn full_type_name(name: &str, current: &str) -> String {
+    if name == "Header" {
+        return "std_msgs/Header".to_string();
+    }
+    match (name.contains('/'), current.split_once('/')) {
+        (false, Some((package, _))) => format!("{}/{}", package, name),
+        _ => name.to_string(),
+    }
+}
+
+/// Parse path to the nested field specified as dot separated field names.
+/// Elements of array fields are specified with the index.
+///
+/// # Examples
+/// ```rust
+/// assert!(parse_field_path("left.image").is_ok());
+/// assert!(parse_field_path("cameras[1].image").is_ok());
+/// ```
+pub fn parse_field_path(value: &str) -> Result<FieldPath, String> {
+    let invalid = || {
+        format!(
+            "`{}` is not a field path, use `field.nested[0].field`",
+            value
+        )
+    };
+    let is_name = |name: &str| {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+    };
+
+    let segments = value
+        .split('.')
+        .map(|segment| {
+            let (name, index) = match segment.split_once('[') {
+                Some((name, index)) => {
+                    let index = index
+                        .strip_suffix(']')
+                        .and_then(|index| index.parse::<usize>().ok())
+                        .ok_or_else(invalid)?;
+                    (name, Some(index))
+                }
+                None => (segment, None),
+            };
+            if !is_name(name) {
+                return Err(invalid());
+            }
+            Ok(PathSegment {
+                name: name.to_string(),
+                index,
+            })
+        })
+        .collect::<Result<Vec<PathSegment>, String>>()?;
+    Ok(FieldPath(segments))
+}
+
+impl Display for FieldPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            if i != 0 {
+                write!(f, ".")?;
+            }
+            write!(f, "{}", segment.name)?;
+            if let Some(index) = segment.index {
+                write!(f, "[{}]", index)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEFINITION: &str = "\
+# Frames of the rig
+Header header
+string note  # comment
+uint8 MODE=1
+uint8[] blob
+float64[2] pose
+Camera left
+Camera[] extra
+================================================================================
+MSG: std_msgs/Header
+uint32 seq
+time stamp
+string frame_id
+================================================================================
+MSG: test_msgs/Camera
+string name
+uint16 id
+";
+
+    fn definition() -> MessageDefinition {
+        MessageDefinition::parse("test_msgs/Rig", DEFINITION).unwrap()
+    }
+
+    fn string(value: &str) -> Vec<u8> {
+        [&(value.len() as u32).to_le_bytes()[..], value.as_bytes()].concat()
+    }
+
+    fn camera(name: &str, id: u16) -> Vec<u8> {
+        [string(name), id.to_le_bytes().to_vec()].concat()
+    }
+
+    /// Message with `extra` cameras `e0` and `e1`
+    fn message() -> Vec<u8> {
+        [
+            vec![7, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0], // seq and stamp
+            string("map"),
+            string("hello"),
+            vec![3, 0, 0, 0, 1, 2, 3],
+            [1f64.to_le_bytes(), 2f64.to_le_bytes()].concat(),
+            camera("left", 1),
+            vec![2, 0, 0, 0],
+            camera("e0", 2),
+            camera("e1", 3),
+        ]
+        .concat()
+    }
+
+    fn path(value: &str) -> FieldPath {
+        parse_field_path(value).unwrap()
+    }
+
+    #[test]
+    fn parse_definition() {
+        let definition = definition();
+        let fields = definition.fields("test_msgs/Rig").unwrap();
+        let names = fields
+            .iter()
+            .map(|f| f.name.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(names, ["header", "note", "blob", "pose", "left", "extra"]);
+        assert_eq!(fields[2].array, Some(Array::Dynamic));
+        assert_eq!(fields[3].array, Some(Array::Fixed(2)));
+        assert_eq!(fields[3].tp, FieldType::Primitive(Primitive::Float64));
+        assert_eq!(fields[0].tp, FieldType::Message("std_msgs/Header".into()));
+        assert_eq!(fields[4].tp, FieldType::Message("test_msgs/Camera".into()));
+        assert_eq!(
+            definition.field_type(&path("left")).unwrap(),
+            "test_msgs/Camera"
+        );
+        assert_eq!(
+            definition.find_fields(&["test_msgs/Camera"]),
+            [path("left"), path("extra[0]")]
+        );
+    }
+
+    #[test]
+    fn parse_invalid_definition() {
+        assert!(MessageDefinition::parse("a/B", "uint8[x] data").is_err());
+        assert!(MessageDefinition::parse("a/B", "uint8").is_err());
+        let definition = MessageDefinition::parse("a/B", "Missing field").unwrap();
+        assert!(definition.locate(&path("field.data"), &[]).is_err());
+    }
+
+    #[test]
+    fn parse_paths() {
+        assert_eq!(path("left.image").to_string(), "left.image");
+        assert_eq!(path("cameras[12].image").to_string(), "cameras[12].image");
+        for invalid in ["", "left.", ".left", "left[", "left[-1]", "left[]", "le ft"] {
+            assert!(parse_field_path(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn locate_fields() {
+        let definition = definition();
+        let message = message();
+        let left = definition.locate(&path("left"), &message).unwrap().unwrap();
+        assert!(left.starts_with(&camera("left", 1)));
+        let extra = definition
+            .locate(&path("extra[1]"), &message)
+            .unwrap()
+            .unwrap();
+        assert_eq!(extra, camera("e1", 3));
+        assert_eq!(
+            definition.locate(&path("extra[2]"), &message).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn locate_invalid_fields() {
+        let definition = definition();
+        let message = message();
+        for invalid in ["right", "extra", "left[0]", "note.name"] {
+            assert!(
+                definition.locate(&path(invalid), &message).is_err(),
+                "{}",
+                invalid
+            );
+        }
+        // message is shorter than the definition
+        assert!(definition.locate(&path("left"), &message[..30]).is_err());
+    }
+
+    #[test]
+    fn decode_message() {
+        let definition = definition();
+        let message = message();
+        let value = definition.decode(&message).unwrap();
+        assert_eq!(value.get_path("header.seq"), Some(&Value::UInt(7)));
+        assert_eq!(
+            value.get_path("header.stamp").and_then(Value::as_time),
+            Some(Time { sec: 1, nsec: 2 })
+        );
+        assert_eq!(value.get("note"), Some(&Value::String("hello")));
+        assert_eq!(value.get("blob"), Some(&Value::Bytes(&[1, 2, 3])));
+        assert_eq!(
+            value.get_path("left.id").and_then(Value::as_f64),
+            Some(1f64)
+        );
+        let extra = value.get("extra").and_then(Value::as_array).unwrap();
+        assert_eq!(extra[1].get("name"), Some(&Value::String("e1")));
+        assert!(definition.decode(&message[..message.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn reject_recursive_definition() {
+        for text in ["Node next\nuint8 value", "Node[2] next\nuint8 value"] {
+            let definition = MessageDefinition::parse("test_msgs/Node", text).unwrap();
+            let message = [0; 64];
+            assert!(definition.locate(&path("value"), &message).is_err());
+            assert!(definition.decode(&message).is_err());
+            assert!(definition.find_fields(&["test_msgs/Other"]).is_empty());
+        }
+    }
+
+    #[test]
+    fn reject_huge_arrays() {
+        let message = [0; 64];
+        let definition =
+            MessageDefinition::parse("a/B", "float64[4611686018427387904] values\nuint8 value")
+                .unwrap();
+        assert!(definition.locate(&path("value"), &message).is_err());
+        assert!(definition.decode(&message).is_err());
+        // the size of the nested type overflows
+        let text = "\
+Big[2] big
+uint8 value
+================================================================================
+MSG: a/Big
+uint8[18446744073709551615] data
+";
+        let definition = MessageDefinition::parse("a/B", text).unwrap();
+        assert!(definition.locate(&path("value"), &message).is_err());
+        assert!(definition.locate(&path("big[1]"), &message).is_err());
+        assert!(definition.decode(&message).is_err());
+    }
+}
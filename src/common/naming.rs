@@ -15,7 +15,7 @@
 /// let res_name = "ic_24_paper_id_left_android_100_".to_string();
 /// assert_eq!(to_res_name(&source_name), res_name);
 /// ```
-pub fn to_res_name(name: &String) -> String {
+pub fn to_res_name(name: &str) -> String {
     let mut output = String::new();
     let mut i = 0u32;
     let mut prev_char_is_uppercase = false;
@@ -0,0 +1,45 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use byteorder::{WriteBytesExt, LE};
+
+/// Write 32-bit float pixels to the file in Portable Float Map format.
+///
+/// PFM is the simplest format which keeps float values as is,
+/// it can be opened with OpenCV (`cv2.imread(path, cv2.IMREAD_UNCHANGED)`),
+/// numpy and most image viewers.
+///
+/// # Arguments
+///
+/// * `path` - path to the output file
+/// * `width`, `height` - image size
+/// * `channels` - number of channels, must be 1 or 3
+/// * `data` - row-major pixels starting from the top-left corner
+pub fn write_pfm(
+    path: &str,
+    width: u32,
+    height: u32,
+    channels: u32,
+    data: &[f32],
+) -> std::io::Result<()> {
+    // rows of an empty image can't be written from bottom to top
+    if width == 0 || height == 0 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "image has no pixels",
+        ));
+    }
+    let mut writer = BufWriter::new(File::create(path)?);
+    let magic = if channels == 3 { "PF" } else { "Pf" };
+    // negative scale means little-endian data
+    write!(writer, "{}\n{} {}\n-1.0\n", magic, width, height)?;
+
+    // PFM rows go from bottom to top
+    let row_len = (width * channels) as usize;
+    for row in data.chunks_exact(row_len).rev() {
+        for value in row {
+            writer.write_f32::<LE>(*value)?;
+        }
+    }
+    writer.flush()
+}
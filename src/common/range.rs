@@ -0,0 +1,58 @@
+/// Range of values which is mapped to the full range of the output image
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueRange {
+    /// Minimum and maximum of every frame
+    Auto,
+    /// The same range for all frames
+    Fixed(f64, f64),
+    /// Percentiles of every frame, values outside are clipped
+    Percentile(f64, f64),
+}
+
+/// Parse value range specified as `auto`, `MIN:MAX` or `pLOW:pHIGH`.
+///
+/// # Examples
+/// ```rust
+/// assert_eq!(parse_range("auto"), Ok(ValueRange::Auto));
+/// assert_eq!(parse_range("0.5:10"), Ok(ValueRange::Fixed(0.5, 10.0)));
+/// assert_eq!(parse_range("p1:p99"), Ok(ValueRange::Percentile(1.0, 99.0)));
+/// ```
+pub fn parse_range(value: &str) -> Result<ValueRange, String> {
+    let invalid = || {
+        format!(
+            "`{}` is not a range, use `auto`, `MIN:MAX` or percentiles `pLOW:pHIGH`",
+            value
+        )
+    };
+    let value = value.trim();
+    if value == "auto" {
+        return Ok(ValueRange::Auto);
+    }
+
+    let (low, high) = value.split_once(':').ok_or_else(invalid)?;
+    let range = match (low.strip_prefix('p'), high.strip_prefix('p')) {
+        (Some(low), Some(high)) => {
+            let low = low.parse::<f64>().map_err(|_| invalid())?;
+            let high = high.parse::<f64>().map_err(|_| invalid())?;
+            if !(0f64..=100f64).contains(&low) || !(0f64..=100f64).contains(&high) {
+                return Err(invalid());
+            }
+            ValueRange::Percentile(low, high)
+        }
+        (None, None) => {
+            let low = low.parse::<f64>().map_err(|_| invalid())?;
+            let high = high.parse::<f64>().map_err(|_| invalid())?;
+            ValueRange::Fixed(low, high)
+        }
+        _ => return Err(invalid()),
+    };
+    match range {
+        ValueRange::Fixed(low, high) | ValueRange::Percentile(low, high) if low >= high => {
+            Err(format!(
+                "`{}` is empty range, minimum must be less than maximum",
+                value
+            ))
+        }
+        _ => Ok(range),
+    }
+}
@@ -0,0 +1,38 @@
+/// Size of the resized image, missing dimension keeps the aspect ratio
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Size {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+/// Parse size specified as `WIDTHxHEIGHT`, `WIDTHx` or `xHEIGHT`.
+///
+/// # Examples
+/// ```rust
+/// assert_eq!(parse_size("640x480"), Ok(Size { width: Some(640), height: Some(480) }));
+/// assert_eq!(parse_size("640x"), Ok(Size { width: Some(640), height: None }));
+/// ```
+pub fn parse_size(value: &str) -> Result<Size, String> {
+    let invalid = || {
+        format!(
+            "`{}` is not a size, use `WIDTHxHEIGHT`, `WIDTHx` or `xHEIGHT`",
+            value
+        )
+    };
+    let (width, height) = value.trim().split_once('x').ok_or_else(invalid)?;
+    let parse = |dimension: &str| match dimension.trim() {
+        "" => Ok(None),
+        dimension => match dimension.parse::<u32>() {
+            Ok(0) | Err(_) => Err(invalid()),
+            Ok(dimension) => Ok(Some(dimension)),
+        },
+    };
+    let size = Size {
+        width: parse(width)?,
+        height: parse(height)?,
+    };
+    if size.width.is_none() && size.height.is_none() {
+        return Err(invalid());
+    }
+    Ok(size)
+}
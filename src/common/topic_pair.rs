@@ -0,0 +1,31 @@
+/// Topic paired with another topic which messages are matched by header stamp
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopicPair {
+    /// Topic of the exported images
+    pub topic: String,
+    /// Topic with additional data for the images, e.g. detections
+    pub paired: String,
+}
+
+/// Parse topic pair specified as `TOPIC=PAIRED`.
+///
+/// # Examples
+/// ```rust
+/// assert_eq!(
+///     parse_topic_pair("/camera/image=/detector/detections"),
+///     Ok(TopicPair { topic: "/camera/image".to_string(), paired: "/detector/detections".to_string() }),
+/// );
+/// ```
+pub fn parse_topic_pair(value: &str) -> Result<TopicPair, String> {
+    let invalid = || format!("`{}` is not a topic pair, use `TOPIC=PAIRED`", value);
+    // topic names can't contain `=`
+    let (topic, paired) = value.split_once('=').ok_or_else(invalid)?;
+    let (topic, paired) = (topic.trim(), paired.trim());
+    if topic.is_empty() || paired.is_empty() || paired.contains('=') {
+        return Err(invalid());
+    }
+    Ok(TopicPair {
+        topic: topic.to_string(),
+        paired: paired.to_string(),
+    })
+}
@@ -0,0 +1,64 @@
+use image::{DynamicImage, ImageBuffer, Rgb, RgbImage};
+
+use crate::common::error::AppError;
+use crate::sensor_msgs::{image_encodings as enc, Image};
+
+use super::debayer::{debayer, BayerPattern};
+use super::{invalid_encoding, pixel_bytes, read_u16, DecodeOptions, Frame, ImageDecoder};
+
+/// Decoder of raw images from bayer sensors
+pub struct BayerDecoder;
+
+impl ImageDecoder for BayerDecoder {
+    fn encodings(&self) -> &[&'static str] {
+        &[
+            enc::BAYER_RGGB8,
+            enc::BAYER_BGGR8,
+            enc::BAYER_GBRG8,
+            enc::BAYER_GRBG8,
+            enc::BAYER_RGGB16,
+            enc::BAYER_BGGR16,
+            enc::BAYER_GBRG16,
+            enc::BAYER_GRBG16,
+        ]
+    }
+
+    fn decode(&self, image: &Image, options: &DecodeOptions) -> Result<Frame, AppError> {
+        let pattern = match image.encoding {
+            enc::BAYER_RGGB8 | enc::BAYER_RGGB16 => BayerPattern::Rggb,
+            enc::BAYER_BGGR8 | enc::BAYER_BGGR16 => BayerPattern::Bggr,
+            enc::BAYER_GBRG8 | enc::BAYER_GBRG16 => BayerPattern::Gbrg,
+            enc::BAYER_GRBG8 | enc::BAYER_GRBG16 => BayerPattern::Grbg,
+            _ => return Err(invalid_encoding(image)),
+        };
+        let (width, height) = (image.width as usize, image.height as usize);
+
+        if image.encoding.ends_with("16") {
+            let raw = read_u16(image, &pixel_bytes(image, 2)?);
+            let rgb = debayer(width, height, &raw, pattern, options.debayer, u16::MAX);
+            let buffer: ImageBuffer<Rgb<u16>, Vec<u16>> =
+                ImageBuffer::from_vec(image.width, image.height, rgb)
+                    .ok_or_else(|| invalid_encoding(image))?;
+            Ok(Frame::Image(DynamicImage::ImageRgb16(buffer)))
+        } else {
+            let raw = pixel_bytes(image, 1)?
+                .iter()
+                .map(|v| *v as u16)
+                .collect::<Vec<u16>>();
+            let rgb = debayer(
+                width,
+                height,
+                &raw,
+                pattern,
+                options.debayer,
+                u8::MAX as u16,
+            )
+            .into_iter()
+            .map(|v| v as u8)
+            .collect::<Vec<u8>>();
+            let buffer: RgbImage = ImageBuffer::from_vec(image.width, image.height, rgb)
+                .ok_or_else(|| invalid_encoding(image))?;
+            Ok(Frame::Image(DynamicImage::ImageRgb8(buffer)))
+        }
+    }
+}
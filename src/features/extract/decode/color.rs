@@ -0,0 +1,107 @@
+use image::{DynamicImage, GrayImage, ImageBuffer, Luma, Rgb, RgbImage, RgbaImage};
+
+use crate::common::error::AppError;
+use crate::sensor_msgs::{image_encodings as enc, Image};
+
+use super::{invalid_encoding, pixel_bytes, read_u16, DecodeOptions, Frame, ImageDecoder};
+
+/// Decoder of 3-channel color images
+pub struct RgbDecoder;
+
+/// Decoder of 4-channel color images with alpha channel
+pub struct RgbaDecoder;
+
+/// Decoder of grayscale images
+pub struct MonoDecoder;
+
+impl ImageDecoder for RgbDecoder {
+    fn encodings(&self) -> &[&'static str] {
+        &[enc::RGB8, enc::BGR8]
+    }
+
+    fn decode(&self, image: &Image, options: &DecodeOptions) -> Result<Frame, AppError> {
+        let bgr_order = (image.encoding == enc::BGR8) != options.invert_channels;
+        let mut buffer: RgbImage = ImageBuffer::from_vec(
+            image.width,
+            image.height,
+            pixel_bytes(image, 3)?.into_owned(),
+        )
+        .ok_or_else(|| invalid_encoding(image))?;
+
+        if bgr_order {
+            for p in buffer.pixels_mut() {
+                let [r, g, b] = p.0;
+                p.0 = [b, g, r];
+            }
+        }
+        Ok(Frame::Image(DynamicImage::ImageRgb8(buffer)))
+    }
+}
+
+impl ImageDecoder for RgbaDecoder {
+    fn encodings(&self) -> &[&'static str] {
+        &[enc::RGBA8, enc::BGRA8]
+    }
+
+    fn decode(&self, image: &Image, options: &DecodeOptions) -> Result<Frame, AppError> {
+        let bgr_order = (image.encoding == enc::BGRA8) != options.invert_channels;
+        let mut buffer: RgbaImage = ImageBuffer::from_vec(
+            image.width,
+            image.height,
+            pixel_bytes(image, 4)?.into_owned(),
+        )
+        .ok_or_else(|| invalid_encoding(image))?;
+
+        if bgr_order {
+            for p in buffer.pixels_mut() {
+                let [r, g, b, a] = p.0;
+                p.0 = [b, g, r, a];
+            }
+        }
+
+        match options.background {
+            Some(background) => Ok(Frame::Image(DynamicImage::ImageRgb8(flatten(
+                &buffer, background,
+            )))),
+            None => Ok(Frame::Image(DynamicImage::ImageRgba8(buffer))),
+        }
+    }
+}
+
+/// Blend transparent image onto the solid background
+fn flatten(image: &RgbaImage, background: Rgb<u8>) -> RgbImage {
+    let blend = |color: u8, alpha: u8, background: u8| {
+        let alpha = alpha as u32;
+        ((color as u32 * alpha + background as u32 * (255 - alpha) + 127) / 255) as u8
+    };
+    ImageBuffer::from_fn(image.width(), image.height(), |x, y| {
+        let [r, g, b, a] = image.get_pixel(x, y).0;
+        let [br, bg, bb] = background.0;
+        Rgb([blend(r, a, br), blend(g, a, bg), blend(b, a, bb)])
+    })
+}
+
+impl ImageDecoder for MonoDecoder {
+    fn encodings(&self) -> &[&'static str] {
+        &[enc::MONO8, enc::MONO16]
+    }
+
+    fn decode(&self, image: &Image, _options: &DecodeOptions) -> Result<Frame, AppError> {
+        let buffer = if image.encoding == enc::MONO16 {
+            let pixels = read_u16(image, &pixel_bytes(image, 2)?);
+            let buffer: ImageBuffer<Luma<u16>, Vec<u16>> =
+                ImageBuffer::from_vec(image.width, image.height, pixels)
+                    .ok_or_else(|| invalid_encoding(image))?;
+            DynamicImage::ImageLuma16(buffer)
+        } else {
+            let buffer: GrayImage = ImageBuffer::from_vec(
+                image.width,
+                image.height,
+                pixel_bytes(image, 1)?.into_owned(),
+            )
+            .ok_or_else(|| invalid_encoding(image))?;
+            DynamicImage::ImageLuma8(buffer)
+        };
+        Ok(Frame::Image(buffer))
+    }
+}
@@ -0,0 +1,213 @@
+use std::io::Cursor;
+
+use byteorder::{ByteOrder, LE};
+use image::ImageFormat;
+
+use crate::common::error::AppError;
+use crate::sensor_msgs::{image_encodings as enc, CompressedImage};
+
+use super::{DecodeOptions, DepthMap, Frame};
+
+/// Size of the `ConfigHeader` struct of compressed_depth_image_transport:
+/// `int32` compression format and `float[2]` depth quantization parameters
+const DEPTH_HEADER_SIZE: usize = 12;
+
+/// Signature of PNG files
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Compressed image which is saved to disk byte-for-byte
+pub struct EncodedImage {
+    /// File extension of the image format
+    pub extension: &'static str,
+    pub data: Vec<u8>,
+}
+
+impl EncodedImage {
+    /// Width and height of the image from its header, the pixels are not decoded
+    pub fn dimensions(&self) -> Result<(u32, u32), AppError> {
+        image::io::Reader::new(Cursor::new(&self.data))
+            .with_guessed_format()
+            .map_err(|e| e.to_string())
+            .and_then(|reader| reader.into_dimensions().map_err(|e| e.to_string()))
+            .map_err(|e| AppError::InvalidCompressedImage(self.extension.to_string(), e))
+    }
+}
+
+/// Decode the `sensor_msgs/CompressedImage` pixels.
+///
+/// The image format is detected by the data itself, because the `format` field
+/// is filled differently by image_transport plugins and other publishers.
+/// Depth images from compressed_depth_image_transport (`16UC1; compressedDepth png`)
+/// are always decoded and handled like raw depth images.
+///
+/// # Arguments
+///
+/// * `image` - compressed image message
+/// * `options` - decoding options for depth images
+/// * `reencode` - decode the pixels for further processing,
+///   otherwise the data is kept as is
+pub fn decode_compressed(
+    image: &CompressedImage,
+    options: &DecodeOptions,
+    reencode: bool,
+) -> Result<Frame, AppError> {
+    let invalid = |cause: String| AppError::InvalidCompressedImage(image.format.to_string(), cause);
+
+    if image.format.contains("compressedDepth") {
+        return decode_compressed_depth(image)
+            .map_err(invalid)?
+            .into_frame(options)
+            .ok_or_else(|| invalid("depth image size mismatch".to_string()));
+    }
+
+    if reencode {
+        return image::load_from_memory(image.data)
+            .map(Frame::Image)
+            .map_err(|e| invalid(e.to_string()));
+    }
+
+    let format = image::guess_format(image.data).map_err(|e| invalid(e.to_string()))?;
+    let extension = format
+        .extensions_str()
+        .first()
+        .ok_or_else(|| invalid("unknown file extension".to_string()))?;
+    Ok(Frame::Encoded(EncodedImage {
+        extension,
+        data: image.data.to_vec(),
+    }))
+}
+
+/// Reconstruct depth compressed by compressed_depth_image_transport.
+///
+/// The format is `{encoding}; compressedDepth {codec}`. The PNG image is prefixed
+/// with the config header, except for bags recorded with old versions of the plugin.
+/// `16UC1` depth is stored as is, `32FC1` depth is quantized as inverse depth:
+/// `depth = a / (value - b)`, zero values mean no data.
+fn decode_compressed_depth(image: &CompressedImage) -> Result<DepthMap, String> {
+    let (encoding, codec) = image
+        .format
+        .split_once(';')
+        .map(|(encoding, codec)| (encoding.trim(), codec.trim()))
+        .ok_or_else(|| "unknown depth encoding".to_string())?;
+    if codec != "compressedDepth" && codec != "compressedDepth png" {
+        return Err(format!(
+            "only PNG compression is supported, found `{}`",
+            codec
+        ));
+    }
+
+    let (quantization, png) = if image.data.starts_with(PNG_SIGNATURE) {
+        (None, image.data)
+    } else if image.data.len() > DEPTH_HEADER_SIZE {
+        let (header, png) = image.data.split_at(DEPTH_HEADER_SIZE);
+        (
+            Some((LE::read_f32(&header[4..8]), LE::read_f32(&header[8..12]))),
+            png,
+        )
+    } else {
+        return Err("missing compressed depth data".to_string());
+    };
+
+    let buffer = image::load_from_memory_with_format(png, ImageFormat::Png)
+        .map_err(|e| e.to_string())?
+        .into_luma16();
+    let (width, height) = buffer.dimensions();
+    let values = buffer.into_raw();
+
+    let (unit, data) = match (encoding, quantization) {
+        (enc::TYPE_16UC1, _) => {
+            let data = values
+                .into_iter()
+                .map(|mm| if mm == 0 { f32::NAN } else { mm as f32 })
+                .collect();
+            (0.001, data)
+        }
+        (enc::TYPE_32FC1, Some((a, b))) => {
+            let data = values
+                .into_iter()
+                .map(|v| if v == 0 { f32::NAN } else { a / (v as f32 - b) })
+                .collect();
+            (1.0, data)
+        }
+        (enc::TYPE_32FC1, None) => {
+            return Err("missing inverse depth quantization parameters".to_string())
+        }
+        (encoding, _) => return Err(format!("unsupported depth encoding `{}`", encoding)),
+    };
+
+    Ok(DepthMap {
+        width,
+        height,
+        unit,
+        data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use image::codecs::png::PngEncoder;
+    use image::{ColorType, ImageEncoder};
+
+    use super::*;
+    use crate::common::de::Time;
+    use crate::sensor_msgs::Header;
+
+    /// 16-bit grayscale PNG with a row of `values`
+    fn png(values: &[u16]) -> Vec<u8> {
+        let bytes = values
+            .iter()
+            .flat_map(|v| v.to_ne_bytes())
+            .collect::<Vec<u8>>();
+        let mut png = Vec::new();
+        PngEncoder::new(&mut png)
+            .write_image(&bytes, values.len() as u32, 1, ColorType::L16)
+            .unwrap();
+        png
+    }
+
+    /// Config header of compressed_depth_image_transport with the quantization parameters
+    fn config_header(a: f32, b: f32) -> Vec<u8> {
+        [0i32.to_le_bytes(), a.to_le_bytes(), b.to_le_bytes()].concat()
+    }
+
+    fn decode<'a>(format: &'a str, data: &'a [u8]) -> Result<DepthMap, String> {
+        decode_compressed_depth(&CompressedImage {
+            header: Header {
+                seq: 0,
+                stamp: Time { sec: 0, nsec: 0 },
+                frame_id: "",
+            },
+            format,
+            data,
+        })
+    }
+
+    #[test]
+    fn decode_depth_in_millimetres() {
+        let with_header = [config_header(0.0, 0.0), png(&[0, 1500])].concat();
+        // old versions of the plugin write the PNG without the header
+        for data in [with_header, png(&[0, 1500])] {
+            let depth = decode("16UC1; compressedDepth png", &data).unwrap();
+            assert_eq!((depth.width, depth.height, depth.unit), (2, 1, 0.001));
+            assert!(depth.data[0].is_nan());
+            assert_eq!(depth.data[1], 1500.0);
+        }
+        let data = png(&[7]);
+        assert!(decode("16UC1; compressedDepth", &data).is_ok());
+        assert!(decode("16UC1; compressedDepth rvl", &data).is_err());
+        assert!(decode("16UC1; compressedDepth png", &data[..10]).is_err());
+    }
+
+    #[test]
+    fn decode_inverse_depth() {
+        // depth = a / (value - b)
+        let data = [config_header(6.0, 1.0), png(&[0, 3, 7])].concat();
+        let depth = decode("32FC1; compressedDepth png", &data).unwrap();
+        assert_eq!(depth.unit, 1.0);
+        assert!(depth.data[0].is_nan());
+        assert_eq!(depth.data[1..], [3.0, 1.0]);
+        // inverse depth can't be restored without the quantization parameters
+        assert!(decode("32FC1; compressedDepth png", &png(&[3])).is_err());
+        assert!(decode("8UC1; compressedDepth png", &png(&[3])).is_err());
+    }
+}
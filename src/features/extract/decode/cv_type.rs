@@ -0,0 +1,300 @@
+use byteorder::{ByteOrder, BE, LE};
+use image::{DynamicImage, ImageBuffer, Luma, LumaA, Rgb, Rgba};
+
+use crate::args::SplitChannels;
+use crate::common::error::AppError;
+use crate::features::extract::tone_map::ToneMapping;
+use crate::sensor_msgs::image_encodings::{self as enc, parse_cv_type, CvDepth};
+use crate::sensor_msgs::Image;
+
+use super::{invalid_encoding, pixel_bytes, DecodeOptions, Frame, ImageDecoder};
+
+/// Decoder of generic OpenCV types (`8UC3`, `16SC1`, `64FC4`, etc).
+///
+/// Conversion rules:
+/// * 1, 2, 3 and 4 channels are treated as gray, gray with alpha, BGR and BGRA,
+///   like OpenCV does it when writing images
+/// * `8U` and `16U` values are kept as is, `8S` and `16S` values are shifted
+///   to the unsigned range
+/// * `32S` values are clamped to the 16-bit range
+/// * `32F` and `64F` values are expected to be in the `[0, 1]` range
+///   and are scaled to the 16-bit range
+/// * `32S`, `32F` and `64F` values are tone mapped to 8-bit images instead,
+///   if tone mapping is enabled
+///
+/// Images with more than 4 channels (`8UC(N)`) can only be exported with
+/// the channels split into separate images.
+///
+/// `16UC1` and `32FC1` are handled by the depth decoder, `32FC2` by the flow decoder.
+pub struct CvTypeDecoder;
+
+impl ImageDecoder for CvTypeDecoder {
+    fn encodings(&self) -> &[&'static str] {
+        &[
+            enc::TYPE_8UC1,
+            enc::TYPE_8UC2,
+            enc::TYPE_8UC3,
+            enc::TYPE_8UC4,
+            enc::TYPE_8SC1,
+            enc::TYPE_8SC2,
+            enc::TYPE_8SC3,
+            enc::TYPE_8SC4,
+            enc::TYPE_16UC2,
+            enc::TYPE_16UC3,
+            enc::TYPE_16UC4,
+            enc::TYPE_16SC1,
+            enc::TYPE_16SC2,
+            enc::TYPE_16SC3,
+            enc::TYPE_16SC4,
+            enc::TYPE_32SC1,
+            enc::TYPE_32SC2,
+            enc::TYPE_32SC3,
+            enc::TYPE_32SC4,
+            enc::TYPE_32FC3,
+            enc::TYPE_32FC4,
+            enc::TYPE_64FC1,
+            enc::TYPE_64FC2,
+            enc::TYPE_64FC3,
+            enc::TYPE_64FC4,
+        ]
+    }
+
+    fn supports(&self, encoding: &str) -> bool {
+        // `16UC1` and `32FC1` are depth images, `32FC2` is optical flow
+        encoding != enc::TYPE_16UC1
+            && encoding != enc::TYPE_32FC1
+            && encoding != enc::TYPE_32FC2
+            && parse_cv_type(encoding).is_some()
+    }
+
+    fn decode(&self, image: &Image, options: &DecodeOptions) -> Result<Frame, AppError> {
+        let (depth, channels) =
+            parse_cv_type(image.encoding).ok_or_else(|| invalid_encoding(image))?;
+        let samples = read_samples(image, depth, &pixel_bytes(image, depth.size() * channels)?);
+        let tone_mapping = options.tone_mapping.as_ref();
+
+        match options.split_channels {
+            Some(mode) => split_channels(image, depth, channels, &samples, mode, tone_mapping)
+                .map(Frame::Split)
+                .ok_or_else(|| invalid_encoding(image)),
+            None if channels > 4 => Err(AppError::TooManyChannels(image.encoding.to_string())),
+            None => {
+                let bgr_order = !options.invert_channels;
+                let (width, height) = (image.width, image.height);
+                let mut samples = samples;
+                if bgr_order && channels >= 3 {
+                    for pixel in samples.chunks_exact_mut(channels) {
+                        pixel.swap(0, 2);
+                    }
+                }
+                samples_to_image(width, height, depth, channels, samples, tone_mapping)
+                    .map(Frame::Image)
+                    .ok_or_else(|| invalid_encoding(image))
+            }
+        }
+    }
+}
+
+/// Split interleaved channels into separate images.
+///
+/// Every image is named by the index of its channel (`ch0`)
+/// or the range of channels for false color images (`ch0-2`).
+fn split_channels(
+    image: &Image,
+    depth: CvDepth,
+    channels: usize,
+    samples: &[f64],
+    mode: SplitChannels,
+    tone_mapping: Option<&ToneMapping>,
+) -> Option<Vec<(String, DynamicImage)>> {
+    let group_size = match mode {
+        SplitChannels::Gray => 1,
+        SplitChannels::Rgb => 3,
+    };
+
+    let mut images = Vec::new();
+    let mut first = 0usize;
+    while first < channels {
+        // the rest of channels which don't fill the triplet are exported as grayscale
+        let size = if first + group_size <= channels {
+            group_size
+        } else {
+            1
+        };
+        let group = samples
+            .chunks_exact(channels)
+            .flat_map(|pixel| &pixel[first..first + size])
+            .copied()
+            .collect::<Vec<f64>>();
+        let suffix = if size == 1 {
+            format!("ch{}", first)
+        } else {
+            format!("ch{}-{}", first, first + size - 1)
+        };
+        let buffer = samples_to_image(image.width, image.height, depth, size, group, tone_mapping)?;
+        images.push((suffix, buffer));
+        first += size;
+    }
+    Some(images)
+}
+
+/// Convert matrix elements to 8-bit and 16-bit images according to the decoder rules
+fn samples_to_image(
+    width: u32,
+    height: u32,
+    depth: CvDepth,
+    channels: usize,
+    samples: Vec<f64>,
+    tone_mapping: Option<&ToneMapping>,
+) -> Option<DynamicImage> {
+    match (depth, tone_mapping) {
+        (CvDepth::S32 | CvDepth::F32 | CvDepth::F64, Some(tone_mapping)) if channels != 2 => {
+            let samples = samples.into_iter().map(|v| v as f32).collect::<Vec<f32>>();
+            tone_mapping.to_image(width, height, channels, &samples)
+        }
+        (CvDepth::U8 | CvDepth::S8, _) => {
+            let samples = samples
+                .into_iter()
+                .map(|v| match depth {
+                    CvDepth::S8 => (v + 128f64) as u8,
+                    _ => v as u8,
+                })
+                .collect::<Vec<u8>>();
+            image_from_samples_8(width, height, channels, samples)
+        }
+        _ => {
+            let samples = samples
+                .into_iter()
+                .map(|v| match depth {
+                    CvDepth::S16 => v + 32768f64,
+                    CvDepth::F32 | CvDepth::F64 if v.is_nan() => 0f64,
+                    CvDepth::F32 | CvDepth::F64 => (v * u16::MAX as f64).round(),
+                    _ => v,
+                })
+                .map(|v| v.clamp(0f64, u16::MAX as f64) as u16)
+                .collect::<Vec<u16>>();
+            image_from_samples_16(width, height, channels, samples)
+        }
+    }
+}
+
+/// Read matrix elements respecting the `is_bigendian` field of the image
+fn read_samples(image: &Image, depth: CvDepth, bytes: &[u8]) -> Vec<f64> {
+    let be = image.is_bigendian;
+    let chunks = bytes.chunks_exact(depth.size());
+    match depth {
+        CvDepth::U8 => chunks.map(|b| b[0] as f64).collect(),
+        CvDepth::S8 => chunks.map(|b| b[0] as i8 as f64).collect(),
+        CvDepth::U16 if be => chunks.map(|b| BE::read_u16(b) as f64).collect(),
+        CvDepth::U16 => chunks.map(|b| LE::read_u16(b) as f64).collect(),
+        CvDepth::S16 if be => chunks.map(|b| BE::read_i16(b) as f64).collect(),
+        CvDepth::S16 => chunks.map(|b| LE::read_i16(b) as f64).collect(),
+        CvDepth::S32 if be => chunks.map(|b| BE::read_i32(b) as f64).collect(),
+        CvDepth::S32 => chunks.map(|b| LE::read_i32(b) as f64).collect(),
+        CvDepth::F32 if be => chunks.map(|b| BE::read_f32(b) as f64).collect(),
+        CvDepth::F32 => chunks.map(|b| LE::read_f32(b) as f64).collect(),
+        CvDepth::F64 if be => chunks.map(BE::read_f64).collect(),
+        CvDepth::F64 => chunks.map(LE::read_f64).collect(),
+    }
+}
+
+fn image_from_samples_8(
+    width: u32,
+    height: u32,
+    channels: usize,
+    samples: Vec<u8>,
+) -> Option<DynamicImage> {
+    match channels {
+        1 => ImageBuffer::<Luma<u8>, _>::from_vec(width, height, samples)
+            .map(DynamicImage::ImageLuma8),
+        2 => ImageBuffer::<LumaA<u8>, _>::from_vec(width, height, samples)
+            .map(DynamicImage::ImageLumaA8),
+        3 => {
+            ImageBuffer::<Rgb<u8>, _>::from_vec(width, height, samples).map(DynamicImage::ImageRgb8)
+        }
+        4 => ImageBuffer::<Rgba<u8>, _>::from_vec(width, height, samples)
+            .map(DynamicImage::ImageRgba8),
+        _ => None,
+    }
+}
+
+fn image_from_samples_16(
+    width: u32,
+    height: u32,
+    channels: usize,
+    samples: Vec<u16>,
+) -> Option<DynamicImage> {
+    match channels {
+        1 => ImageBuffer::<Luma<u16>, _>::from_vec(width, height, samples)
+            .map(DynamicImage::ImageLuma16),
+        2 => ImageBuffer::<LumaA<u16>, _>::from_vec(width, height, samples)
+            .map(DynamicImage::ImageLumaA16),
+        3 => ImageBuffer::<Rgb<u16>, _>::from_vec(width, height, samples)
+            .map(DynamicImage::ImageRgb16),
+        4 => ImageBuffer::<Rgba<u16>, _>::from_vec(width, height, samples)
+            .map(DynamicImage::ImageRgba16),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{image, options};
+    use super::super::DecoderRegistry;
+    use super::*;
+
+    /// Single pixel image decoded by the generic decoder
+    fn decode(encoding: &str, data: &[u8]) -> DynamicImage {
+        let image = image(encoding, 1, 1, data.len() as u32, data);
+        match CvTypeDecoder.decode(&image, &options()).unwrap() {
+            Frame::Image(image) => image,
+            _ => panic!("{} image is not decoded as image", encoding),
+        }
+    }
+
+    #[test]
+    fn leave_depth_and_flow_to_other_decoders() {
+        for encoding in [enc::TYPE_16UC1, enc::TYPE_32FC1, enc::TYPE_32FC2] {
+            assert!(!CvTypeDecoder.supports(encoding), "{}", encoding);
+        }
+        assert!(CvTypeDecoder.supports(enc::TYPE_16UC2));
+        assert!(CvTypeDecoder.supports("8UC5"));
+        assert!(!CvTypeDecoder.supports("8UC0"));
+
+        let registry = DecoderRegistry::default();
+        let data = [0; 8];
+        let decode = |encoding, step| {
+            registry
+                .decode(&image(encoding, 1, 1, step, &data), &options())
+                .unwrap()
+        };
+        assert!(matches!(decode(enc::TYPE_16UC1, 2), Frame::Depth(_)));
+        assert!(matches!(decode(enc::TYPE_32FC1, 4), Frame::Depth(_)));
+        assert!(matches!(decode(enc::TYPE_32FC2, 8), Frame::Flow(_)));
+        assert!(matches!(
+            decode(enc::TYPE_16UC2, 4),
+            Frame::Image(DynamicImage::ImageLumaA16(_))
+        ));
+    }
+
+    #[test]
+    fn convert_values() {
+        // signed values are shifted to the unsigned range
+        let image = decode(enc::TYPE_8SC1, &[0x80]);
+        assert_eq!(image.into_luma8().into_raw(), [0]);
+        let image = decode(enc::TYPE_16SC1, &(-1i16).to_le_bytes());
+        assert_eq!(image.into_luma16().into_raw(), [32767]);
+        let image = decode(enc::TYPE_32SC1, &70000i32.to_le_bytes());
+        assert_eq!(image.into_luma16().into_raw(), [u16::MAX]);
+
+        // floats are scaled from `[0, 1]`, channels are in BGR order
+        let bgr = [0f32, 0.5, 1.0]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect::<Vec<u8>>();
+        let image = decode(enc::TYPE_32FC3, &bgr);
+        assert_eq!(image.into_rgb16().into_raw(), [u16::MAX, 32768, 0]);
+        let image = decode(enc::TYPE_64FC1, &f64::NAN.to_le_bytes());
+        assert_eq!(image.into_luma16().into_raw(), [0]);
+    }
+}
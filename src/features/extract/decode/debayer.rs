@@ -0,0 +1,287 @@
+use crate::args::DebayerMethod;
+
+/// Order of the color filters in the top-left 2x2 block of the sensor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BayerPattern {
+    Rggb,
+    Bggr,
+    Gbrg,
+    Grbg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Color {
+    Red,
+    Green,
+    Blue,
+}
+
+/// Single channel raw sensor image
+struct Mosaic<'a> {
+    width: usize,
+    height: usize,
+    data: &'a [u16],
+    /// Position of the red pixel in the 2x2 block
+    red: (usize, usize),
+}
+
+impl BayerPattern {
+    fn red_position(&self) -> (usize, usize) {
+        match self {
+            BayerPattern::Rggb => (0, 0),
+            BayerPattern::Bggr => (1, 1),
+            BayerPattern::Gbrg => (0, 1),
+            BayerPattern::Grbg => (1, 0),
+        }
+    }
+}
+
+impl<'a> Mosaic<'a> {
+    fn color(&self, x: usize, y: usize) -> Color {
+        let (rx, ry) = self.red;
+        match ((x & 1) == rx, (y & 1) == ry) {
+            (true, true) => Color::Red,
+            (false, false) => Color::Blue,
+            _ => Color::Green,
+        }
+    }
+
+    /// Pixel value with mirrored borders, mirroring keeps the color of the pixel
+    fn get(&self, x: isize, y: isize) -> i32 {
+        let x = mirror(x, self.width);
+        let y = mirror(y, self.height);
+        self.data[y * self.width + x] as i32
+    }
+}
+
+/// Index mirrored into `0..n`, reflections repeat for images smaller than the kernel
+fn mirror(i: isize, n: usize) -> usize {
+    if n == 1 {
+        return 0;
+    }
+    let period = 2 * n as isize - 2;
+    let i = i.rem_euclid(period);
+    (if i >= n as isize { period - i } else { i }) as usize
+}
+
+/// Convert raw sensor pixels into interleaved RGB pixels.
+///
+/// # Arguments
+///
+/// * `width`, `height` - image size
+/// * `data` - raw pixels, `width * height` values
+/// * `pattern` - order of the color filters
+/// * `method` - demosaicing algorithm
+/// * `max_value` - maximum pixel value (255 for 8-bit images)
+pub fn debayer(
+    width: usize,
+    height: usize,
+    data: &[u16],
+    pattern: BayerPattern,
+    method: DebayerMethod,
+    max_value: u16,
+) -> Vec<u16> {
+    let mosaic = Mosaic {
+        width,
+        height,
+        data,
+        red: pattern.red_position(),
+    };
+    let green = match method {
+        DebayerMethod::Bilinear => interpolate_green_bilinear(&mosaic),
+        DebayerMethod::EdgeAware => interpolate_green_edge_aware(&mosaic),
+    };
+
+    let mut output = Vec::with_capacity(width * height * 3);
+    for y in 0..height {
+        for x in 0..width {
+            let g = green[y * width + x];
+            let r = interpolate_color(&mosaic, &green, method, Color::Red, x, y);
+            let b = interpolate_color(&mosaic, &green, method, Color::Blue, x, y);
+            let max_value = max_value as i32;
+            output.push(r.clamp(0, max_value) as u16);
+            output.push(g.clamp(0, max_value) as u16);
+            output.push(b.clamp(0, max_value) as u16);
+        }
+    }
+    output
+}
+
+fn interpolate_green_bilinear(m: &Mosaic) -> Vec<i32> {
+    let mut green = Vec::with_capacity(m.width * m.height);
+    for y in 0..m.height {
+        for x in 0..m.width {
+            let (xi, yi) = (x as isize, y as isize);
+            let value = if m.color(x, y) == Color::Green {
+                m.get(xi, yi)
+            } else {
+                (m.get(xi - 1, yi) + m.get(xi + 1, yi) + m.get(xi, yi - 1) + m.get(xi, yi + 1)) / 4
+            };
+            green.push(value);
+        }
+    }
+    green
+}
+
+fn interpolate_green_edge_aware(m: &Mosaic) -> Vec<i32> {
+    let mut green = Vec::with_capacity(m.width * m.height);
+    for y in 0..m.height {
+        for x in 0..m.width {
+            let (xi, yi) = (x as isize, y as isize);
+            if m.color(x, y) == Color::Green {
+                green.push(m.get(xi, yi));
+                continue;
+            }
+            let c = m.get(xi, yi);
+            let (w, e) = (m.get(xi - 1, yi), m.get(xi + 1, yi));
+            let (n, s) = (m.get(xi, yi - 1), m.get(xi, yi + 1));
+            let laplace_h = 2 * c - m.get(xi - 2, yi) - m.get(xi + 2, yi);
+            let laplace_v = 2 * c - m.get(xi, yi - 2) - m.get(xi, yi + 2);
+            let gradient_h = (w - e).abs() + laplace_h.abs();
+            let gradient_v = (n - s).abs() + laplace_v.abs();
+            let green_h = (w + e) / 2 + laplace_h / 4;
+            let green_v = (n + s) / 2 + laplace_v / 4;
+            let value = match gradient_h.cmp(&gradient_v) {
+                std::cmp::Ordering::Less => green_h,
+                std::cmp::Ordering::Greater => green_v,
+                std::cmp::Ordering::Equal => (green_h + green_v) / 2,
+            };
+            green.push(value);
+        }
+    }
+    green
+}
+
+/// Interpolate red or blue value of the pixel.
+///
+/// The bilinear method averages neighbour values,
+/// the edge-aware method averages color differences with the green plane,
+/// which reduces color fringes on the edges.
+fn interpolate_color(
+    m: &Mosaic,
+    green: &[i32],
+    method: DebayerMethod,
+    color: Color,
+    x: usize,
+    y: usize,
+) -> i32 {
+    let (xi, yi) = (x as isize, y as isize);
+    let pixel_color = m.color(x, y);
+    if pixel_color == color {
+        return m.get(xi, yi);
+    }
+
+    let neighbours: &[(isize, isize)] = match pixel_color {
+        // green pixel, the color is either in the same row or in the same column
+        Color::Green if m.color(mirror(xi - 1, m.width), y) == color => &[(-1, 0), (1, 0)],
+        Color::Green => &[(0, -1), (0, 1)],
+        // red pixel for blue color and vice versa, the color is on the diagonals
+        _ => &[(-1, -1), (1, -1), (-1, 1), (1, 1)],
+    };
+
+    let count = neighbours.len() as i32;
+    match method {
+        DebayerMethod::Bilinear => {
+            neighbours
+                .iter()
+                .map(|(dx, dy)| m.get(xi + dx, yi + dy))
+                .sum::<i32>()
+                / count
+        }
+        DebayerMethod::EdgeAware => {
+            let green_at =
+                |x: isize, y: isize| green[mirror(y, m.height) * m.width + mirror(x, m.width)];
+            let difference = neighbours
+                .iter()
+                .map(|(dx, dy)| m.get(xi + dx, yi + dy) - green_at(xi + dx, yi + dy))
+                .sum::<i32>()
+                / count;
+            green[y * m.width + x] + difference
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATTERNS: [BayerPattern; 4] = [
+        BayerPattern::Rggb,
+        BayerPattern::Bggr,
+        BayerPattern::Gbrg,
+        BayerPattern::Grbg,
+    ];
+
+    const METHODS: [DebayerMethod; 2] = [DebayerMethod::Bilinear, DebayerMethod::EdgeAware];
+
+    /// Raw pixels of the sensor which sees a scene of the single `rgb` color
+    fn flat_mosaic(width: usize, height: usize, pattern: BayerPattern, rgb: [u16; 3]) -> Vec<u16> {
+        let mosaic = Mosaic {
+            width,
+            height,
+            data: &[],
+            red: pattern.red_position(),
+        };
+        let mut data = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                data.push(match mosaic.color(x, y) {
+                    Color::Red => rgb[0],
+                    Color::Green => rgb[1],
+                    Color::Blue => rgb[2],
+                });
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn debayer_flat_scene() {
+        // mirrored borders keep the colors of odd-sized images
+        for (width, height) in [(2, 2), (3, 3), (5, 2), (2, 5), (7, 3)] {
+            for pattern in PATTERNS {
+                for method in METHODS {
+                    let data = flat_mosaic(width, height, pattern, [200, 120, 40]);
+                    let rgb = debayer(width, height, &data, pattern, method, 255);
+                    assert_eq!(
+                        rgb,
+                        [200, 120, 40].repeat(width * height),
+                        "{}x{} {:?} {:?}",
+                        width,
+                        height,
+                        pattern,
+                        method
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn debayer_one_pixel_images() {
+        for pattern in PATTERNS {
+            for method in METHODS {
+                // the only pixel has no neighbours of other colors
+                assert_eq!(debayer(1, 1, &[77], pattern, method, 255), [77, 77, 77]);
+                for (width, height) in [(1, 4), (4, 1), (1, 3), (3, 1)] {
+                    let data = (0..width * height)
+                        .map(|i| i as u16 * 50)
+                        .collect::<Vec<u16>>();
+                    let rgb = debayer(width, height, &data, pattern, method, 255);
+                    assert_eq!(rgb.len(), width * height * 3);
+                    assert!(rgb.iter().all(|v| *v <= 255));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn debayer_clamps_values() {
+        // edge-aware interpolation overshoots on sharp edges
+        let data = [0, 4095, 0, 4095, 4095, 0, 4095, 0, 0, 4095, 0, 4095];
+        for method in METHODS {
+            let rgb = debayer(4, 3, &data, BayerPattern::Rggb, method, 4095);
+            assert!(rgb.iter().all(|v| *v <= 4095), "{:?}", method);
+        }
+    }
+}
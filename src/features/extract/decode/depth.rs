@@ -0,0 +1,97 @@
+use crate::common::error::AppError;
+use crate::sensor_msgs::{image_encodings as enc, Image};
+
+use super::{
+    invalid_encoding, pixel_bytes, read_f32, read_u16, DecodeOptions, Frame, ImageDecoder,
+};
+
+/// Depth image according to [REP 118](https://www.ros.org/reps/rep-0118.html).
+///
+/// Values are kept in units of the source image to avoid rounding errors,
+/// pixels without depth data are `NaN`.
+pub struct DepthMap {
+    pub width: u32,
+    pub height: u32,
+    /// Size of the value unit in metres
+    pub unit: f64,
+    pub data: Vec<f32>,
+}
+
+/// Decoder of depth images
+pub struct DepthDecoder;
+
+impl ImageDecoder for DepthDecoder {
+    fn encodings(&self) -> &[&'static str] {
+        &[enc::TYPE_16UC1, enc::TYPE_32FC1]
+    }
+
+    fn decode(&self, image: &Image, options: &DecodeOptions) -> Result<Frame, AppError> {
+        let depth = if image.encoding == enc::TYPE_16UC1 {
+            // `16UC1` depth is in millimetres, zero means no data
+            let data = read_u16(image, &pixel_bytes(image, 2)?)
+                .into_iter()
+                .map(|mm| if mm == 0 { f32::NAN } else { mm as f32 })
+                .collect::<Vec<f32>>();
+            DepthMap::new(image, 0.001, data)?
+        } else {
+            // `32FC1` depth is in metres, `NaN` means no data
+            let data = read_f32(image, &pixel_bytes(image, 4)?);
+            DepthMap::new(image, 1.0, data)?
+        };
+
+        depth
+            .into_frame(options)
+            .ok_or_else(|| invalid_encoding(image))
+    }
+}
+
+impl DepthMap {
+    fn new(image: &Image, unit: f64, data: Vec<f32>) -> Result<Self, AppError> {
+        if data.len() != image.width as usize * image.height as usize {
+            return Err(invalid_encoding(image));
+        }
+        Ok(Self {
+            width: image.width,
+            height: image.height,
+            unit,
+            data,
+        })
+    }
+
+    /// Depth map as is, or rendered as viewable image if tone mapping is enabled
+    pub(super) fn into_frame(self, options: &DecodeOptions) -> Option<Frame> {
+        match &options.tone_mapping {
+            Some(tone_mapping) => tone_mapping
+                .to_image(self.width, self.height, 1, &self.data)
+                .map(Frame::Image),
+            None => Some(Frame::Depth(self)),
+        }
+    }
+
+    /// Depth values converted to another unit
+    ///
+    /// # Arguments
+    ///
+    /// * `unit` - size of the target unit in metres
+    pub fn to_units(&self, unit: f64) -> Vec<f32> {
+        let scale = self.unit / unit;
+        self.data
+            .iter()
+            .map(|value| (*value as f64 * scale) as f32)
+            .collect()
+    }
+
+    /// Depth in millimetres as it is stored in `16UC1` images
+    pub fn to_millimetres(&self) -> Vec<u16> {
+        self.to_units(0.001)
+            .iter()
+            .map(|mm| {
+                if mm.is_finite() && *mm > 0f32 {
+                    mm.round().min(u16::MAX as f32) as u16
+                } else {
+                    0u16
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,92 @@
+use image::{DynamicImage, ImageBuffer, Rgb};
+
+use crate::args::Palette;
+use crate::common::error::AppError;
+use crate::common::range::ValueRange;
+use crate::features::extract::palette::colorize;
+use crate::features::extract::tone_map::ToneMapping;
+use crate::sensor_msgs::image_encodings as enc;
+use crate::stereo_msgs::DisparityImage;
+
+use super::{invalid_encoding, pixel_bytes, read_f32, DecodeOptions, DepthMap, Frame};
+
+/// Decode the `stereo_msgs/DisparityImage` pixels.
+///
+/// Disparities are rendered with the rainbow palette in the range searched by the stereo
+/// algorithm (`min_disparity..max_disparity`), like `disparity_view` of image_view does it.
+/// Values outside the range are invalid and rendered with the `NaN` color.
+///
+/// # Arguments
+///
+/// * `disparity` - disparity image message
+/// * `options` - decoding options, depth is handled like raw depth images
+/// * `to_depth` - convert the disparity to metric depth `Z = fT / d`
+pub fn decode_disparity(
+    disparity: &DisparityImage,
+    options: &DecodeOptions,
+    to_depth: bool,
+) -> Result<Frame, AppError> {
+    let image = &disparity.image;
+    if image.encoding != enc::TYPE_32FC1 {
+        return Err(invalid_encoding(image));
+    }
+    let data = read_f32(image, &pixel_bytes(image, 4)?)
+        .into_iter()
+        .map(|d| {
+            if d >= disparity.min_disparity && d <= disparity.max_disparity {
+                d
+            } else {
+                f32::NAN
+            }
+        })
+        .collect::<Vec<f32>>();
+    if data.len() != image.width as usize * image.height as usize {
+        return Err(invalid_encoding(image));
+    }
+
+    if to_depth {
+        let focal_baseline = disparity.f * disparity.t;
+        let depth = DepthMap {
+            width: image.width,
+            height: image.height,
+            unit: 1.0,
+            data: data
+                .into_iter()
+                .map(|d| {
+                    if d > 0f32 {
+                        focal_baseline / d
+                    } else {
+                        f32::NAN
+                    }
+                })
+                .collect(),
+        };
+        return depth
+            .into_frame(options)
+            .ok_or_else(|| invalid_encoding(image));
+    }
+
+    let tone_mapping = ToneMapping {
+        range: ValueRange::Fixed(
+            disparity.min_disparity as f64,
+            disparity.max_disparity as f64,
+        ),
+        fill: options
+            .tone_mapping
+            .as_ref()
+            .map(|tone_mapping| tone_mapping.fill)
+            .unwrap_or(Rgb([0, 0, 0])),
+        log_scale: false,
+    };
+    let pixels = tone_mapping
+        .normalize(&data)
+        .into_iter()
+        .flat_map(|t| match t {
+            Some(t) => colorize(Palette::Rainbow, t),
+            None => tone_mapping.fill.0,
+        })
+        .collect::<Vec<u8>>();
+    ImageBuffer::<Rgb<u8>, _>::from_vec(image.width, image.height, pixels)
+        .map(|buffer| Frame::Image(DynamicImage::ImageRgb8(buffer)))
+        .ok_or_else(|| invalid_encoding(image))
+}
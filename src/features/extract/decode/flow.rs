@@ -0,0 +1,108 @@
+use std::f32::consts::PI;
+
+use image::{DynamicImage, ImageBuffer, Rgb};
+
+use crate::common::error::AppError;
+use crate::sensor_msgs::{image_encodings as enc, Image};
+
+use super::{invalid_encoding, pixel_bytes, read_f32, DecodeOptions, Frame, ImageDecoder};
+
+/// Flow components with greater absolute values are unknown, as in the Middlebury dataset
+const UNKNOWN_FLOW: f32 = 1e9;
+
+/// Dense optical flow, every pixel is the `(u, v)` motion vector in pixels
+pub struct FlowField {
+    pub width: u32,
+    pub height: u32,
+    /// Row-major interleaved `u` and `v` components
+    pub data: Vec<f32>,
+}
+
+/// Decoder of optical flow images
+pub struct FlowDecoder;
+
+impl ImageDecoder for FlowDecoder {
+    fn encodings(&self) -> &[&'static str] {
+        &[enc::TYPE_32FC2]
+    }
+
+    fn decode(&self, image: &Image, _: &DecodeOptions) -> Result<Frame, AppError> {
+        let data = read_f32(image, &pixel_bytes(image, 8)?);
+        if data.len() != image.width as usize * image.height as usize * 2 {
+            return Err(invalid_encoding(image));
+        }
+        Ok(Frame::Flow(FlowField {
+            width: image.width,
+            height: image.height,
+            data,
+        }))
+    }
+}
+
+impl FlowField {
+    /// Render the flow with the HSV color wheel: direction as hue and magnitude as brightness.
+    /// Unknown vectors are black.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_magnitude` - magnitude rendered with the full brightness,
+    ///   the maximum magnitude of the frame if it's not specified
+    pub fn to_image(&self, max_magnitude: Option<f32>) -> DynamicImage {
+        let vectors = self
+            .data
+            .chunks_exact(2)
+            .map(|uv| {
+                let (u, v) = (uv[0], uv[1]);
+                if u.is_finite()
+                    && v.is_finite()
+                    && u.abs() < UNKNOWN_FLOW
+                    && v.abs() < UNKNOWN_FLOW
+                {
+                    Some((u, v))
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<Option<(f32, f32)>>>();
+
+        let max_magnitude = max_magnitude.unwrap_or_else(|| {
+            vectors
+                .iter()
+                .flatten()
+                .map(|(u, v)| u.hypot(*v))
+                .fold(0f32, f32::max)
+        });
+
+        let pixels = vectors
+            .iter()
+            .flat_map(|vector| match vector {
+                Some((u, v)) if max_magnitude > 0f32 => {
+                    let hue = v.atan2(*u).rem_euclid(2f32 * PI) / (2f32 * PI) * 360f32;
+                    let value = (u.hypot(*v) / max_magnitude).min(1f32);
+                    hsv_to_rgb(hue, value)
+                }
+                _ => [0u8; 3],
+            })
+            .collect::<Vec<u8>>();
+
+        let buffer = ImageBuffer::<Rgb<u8>, _>::from_vec(self.width, self.height, pixels)
+            .expect("flow field size is checked when decoding");
+        DynamicImage::ImageRgb8(buffer)
+    }
+}
+
+/// Fully saturated color with the hue in degrees and the value in `[0, 1]`
+fn hsv_to_rgb(hue: f32, value: f32) -> [u8; 3] {
+    let sector = hue / 60f32;
+    let fraction = sector - sector.floor();
+    let (rising, falling) = (value * fraction, value * (1f32 - fraction));
+    let (r, g, b) = match sector as u32 % 6 {
+        0 => (value, rising, 0f32),
+        1 => (falling, value, 0f32),
+        2 => (0f32, value, rising),
+        3 => (0f32, falling, value),
+        4 => (rising, 0f32, value),
+        _ => (value, 0f32, falling),
+    };
+    [r, g, b].map(|c| (c * 255f32).round() as u8)
+}
@@ -0,0 +1,41 @@
+use image::{DynamicImage, GrayImage, ImageBuffer, Luma};
+
+use crate::common::error::AppError;
+use crate::sensor_msgs::{image_encodings as enc, Image};
+
+use super::{invalid_encoding, pixel_bytes, read_u16, DecodeOptions, Frame, ImageDecoder};
+
+/// Decoder of semantic segmentation masks with a class id in every pixel.
+///
+/// Class ids are kept as 8-bit or 16-bit grayscale values to be colorized with the palette.
+/// Images from topics which are not marked as segmentation are left to the usual decoders.
+pub struct LabelMapDecoder;
+
+impl ImageDecoder for LabelMapDecoder {
+    fn encodings(&self) -> &[&'static str] {
+        &[enc::MONO8, enc::TYPE_8UC1, enc::MONO16, enc::TYPE_16UC1]
+    }
+
+    fn accepts(&self, options: &DecodeOptions) -> bool {
+        options.segmentation
+    }
+
+    fn decode(&self, image: &Image, _options: &DecodeOptions) -> Result<Frame, AppError> {
+        let buffer = if image.encoding == enc::MONO16 || image.encoding == enc::TYPE_16UC1 {
+            let pixels = read_u16(image, &pixel_bytes(image, 2)?);
+            let buffer: ImageBuffer<Luma<u16>, Vec<u16>> =
+                ImageBuffer::from_vec(image.width, image.height, pixels)
+                    .ok_or_else(|| invalid_encoding(image))?;
+            DynamicImage::ImageLuma16(buffer)
+        } else {
+            let buffer: GrayImage = ImageBuffer::from_vec(
+                image.width,
+                image.height,
+                pixel_bytes(image, 1)?.into_owned(),
+            )
+            .ok_or_else(|| invalid_encoding(image))?;
+            DynamicImage::ImageLuma8(buffer)
+        };
+        Ok(Frame::Image(buffer))
+    }
+}
@@ -0,0 +1,297 @@
+use std::borrow::Cow;
+
+use byteorder::{ByteOrder, BE, LE};
+use image::{DynamicImage, Rgb};
+
+use crate::args::{Args, DebayerMethod, SplitChannels, YuvMatrix};
+use crate::common::error::AppError;
+use crate::common::range::ValueRange;
+use crate::features::extract::tone_map::ToneMapping;
+use crate::sensor_msgs::Image;
+
+mod bayer;
+mod color;
+mod compressed;
+mod cv_type;
+mod debayer;
+mod depth;
+mod disparity;
+mod flow;
+mod label_map;
+mod point_cloud;
+mod registry;
+mod thermal;
+mod yuv;
+
+pub use self::compressed::{decode_compressed, EncodedImage};
+pub use self::depth::DepthMap;
+pub use self::disparity::decode_disparity;
+pub use self::flow::FlowField;
+pub use self::point_cloud::decode_point_cloud;
+pub use self::registry::DecoderRegistry;
+pub use self::thermal::ThermalMap;
+
+/// Decoded pixels of the images from all supported messages
+pub enum Frame {
+    /// Image which can be saved to disk as is
+    Image(DynamicImage),
+    /// Depth map which requires special handling to keep the real values
+    Depth(DepthMap),
+    /// Optical flow which can be rendered or saved with the raw vectors
+    Flow(FlowField),
+    /// Temperature from thermal camera which can be rendered with a palette
+    Thermal(ThermalMap),
+    /// Compressed image which is saved without re-encoding
+    Encoded(EncodedImage),
+    /// Several images from one frame, each with the suffix of its file name
+    Split(Vec<(String, DynamicImage)>),
+}
+
+impl Frame {
+    /// Width and height of the frame in pixels, split images have the same size.
+    /// Compressed images are read up to the header with the size.
+    pub fn dimensions(&self) -> Result<(u32, u32), AppError> {
+        Ok(match self {
+            Self::Image(image) => (image.width(), image.height()),
+            Self::Depth(depth) => (depth.width, depth.height),
+            Self::Flow(flow) => (flow.width, flow.height),
+            Self::Thermal(thermal) => (thermal.width, thermal.height),
+            Self::Encoded(encoded) => encoded.dimensions()?,
+            Self::Split(images) => images
+                .first()
+                .map(|(_, image)| (image.width(), image.height()))
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// Options which affect how the pixels are decoded
+#[derive(Debug, Clone)]
+pub struct DecodeOptions {
+    /// The image has mixed up color channels, so `rgb8` is actually `bgr8` and vice versa
+    pub invert_channels: bool,
+    /// Demosaicing algorithm for bayer images
+    pub debayer: DebayerMethod,
+    /// Background color for flattening transparent images
+    pub background: Option<Rgb<u8>>,
+    /// Color matrix for YUV images
+    pub yuv_matrix: YuvMatrix,
+    /// Render float and depth images as viewable images
+    pub tone_mapping: Option<ToneMapping>,
+    /// Export channels of generic OpenCV type images separately
+    pub split_channels: Option<SplitChannels>,
+    /// Calibration of the thermal camera, if the image contains radiometric counts
+    pub thermal: Option<ThermalCalibration>,
+    /// The image is a segmentation mask with class ids, which are kept as is
+    pub segmentation: bool,
+}
+
+/// Linear conversion from thermal camera counts to degrees Celsius
+#[derive(Debug, Clone, Copy)]
+pub struct ThermalCalibration {
+    pub gain: f64,
+    pub offset: f64,
+}
+
+impl DecodeOptions {
+    /// # Arguments
+    ///
+    /// * `args` - app arguments with decoding options
+    /// * `invert_channels` - the topic has mixed up color channels
+    /// * `thermal` - the topic is from thermal camera
+    /// * `segmentation` - the topic contains segmentation masks
+    pub fn new(args: &Args, invert_channels: bool, thermal: bool, segmentation: bool) -> Self {
+        Self {
+            invert_channels,
+            debayer: args.debayer,
+            background: args.background,
+            yuv_matrix: args.yuv_matrix,
+            tone_mapping: tone_mapping(args),
+            split_channels: args.split_channels,
+            thermal: if thermal {
+                Some(ThermalCalibration {
+                    gain: args.thermal_gain,
+                    offset: args.thermal_offset,
+                })
+            } else {
+                None
+            },
+            segmentation,
+        }
+    }
+}
+
+/// Tone mapping is enabled by any of its options, the range is `auto` by default
+fn tone_mapping(args: &Args) -> Option<ToneMapping> {
+    if args.range.is_none() && args.nan_color.is_none() && !args.log_scale {
+        return None;
+    }
+    Some(ToneMapping {
+        range: args.range.unwrap_or(ValueRange::Auto),
+        fill: args.nan_color.unwrap_or(Rgb([0, 0, 0])),
+        log_scale: args.log_scale,
+    })
+}
+
+/// Decoder of the `sensor_msgs/Image` pixels with specific encodings.
+///
+/// To support a new encoding, implement this trait and add the decoder
+/// to the registry with [DecoderRegistry::register].
+pub trait ImageDecoder {
+    /// Encodings supported by the decoder, as they are written in the `encoding` field
+    fn encodings(&self) -> &[&'static str];
+
+    /// Can the decoder convert images with the `encoding`?
+    fn supports(&self, encoding: &str) -> bool {
+        self.encodings().contains(&encoding)
+    }
+
+    /// Does the decoder handle the images with the `options`?
+    /// Otherwise the registry falls back to an earlier decoder of the encoding
+    fn accepts(&self, _options: &DecodeOptions) -> bool {
+        true
+    }
+
+    /// Convert pixels of the `image` into a [Frame]
+    fn decode(&self, image: &Image, options: &DecodeOptions) -> Result<Frame, AppError>;
+}
+
+fn invalid_encoding(image: &Image) -> AppError {
+    AppError::InvalidImageEncoding(image.encoding.to_string())
+}
+
+/// Pixel bytes without the padding at the end of each row.
+///
+/// Drivers may align rows in memory, so `step` can be greater
+/// than `width * bytes_per_pixel`.
+fn pixel_bytes<'a>(image: &Image<'a>, bytes_per_pixel: usize) -> Result<Cow<'a, [u8]>, AppError> {
+    let row_len = image.width as usize * bytes_per_pixel;
+    let step = image.step as usize;
+    let height = image.height as usize;
+    if step < row_len || image.data.len() < (step * height).saturating_sub(step - row_len) {
+        return Err(invalid_encoding(image));
+    }
+
+    if step == row_len {
+        return Ok(Cow::Borrowed(&image.data[..row_len * height]));
+    }
+    let pixels = image
+        .data
+        .chunks(step)
+        .take(height)
+        .flat_map(|row| &row[..row_len])
+        .copied()
+        .collect::<Vec<u8>>();
+    Ok(Cow::Owned(pixels))
+}
+
+/// Read 16-bit values respecting the `is_bigendian` field of the image
+fn read_u16(image: &Image, bytes: &[u8]) -> Vec<u16> {
+    let read = if image.is_bigendian {
+        BE::read_u16
+    } else {
+        LE::read_u16
+    };
+    bytes.chunks_exact(2).map(read).collect()
+}
+
+/// Read 32-bit floats respecting the `is_bigendian` field of the image
+fn read_f32(image: &Image, bytes: &[u8]) -> Vec<f32> {
+    let read = if image.is_bigendian {
+        BE::read_f32
+    } else {
+        LE::read_f32
+    };
+    bytes.chunks_exact(4).map(read).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::depth::DepthDecoder;
+    use super::*;
+    use crate::common::de::Time;
+    use crate::sensor_msgs::{image_encodings as enc, Header};
+
+    /// Little-endian image message with the pixels in `data`
+    pub(super) fn image<'a>(
+        encoding: &'a str,
+        width: u32,
+        height: u32,
+        step: u32,
+        data: &'a [u8],
+    ) -> Image<'a> {
+        Image {
+            header: Header {
+                seq: 0,
+                stamp: Time { sec: 0, nsec: 0 },
+                frame_id: "",
+            },
+            height,
+            width,
+            encoding,
+            is_bigendian: false,
+            step,
+            data,
+        }
+    }
+
+    /// Options of the app run without decoding flags
+    pub(super) fn options() -> DecodeOptions {
+        DecodeOptions {
+            invert_channels: false,
+            debayer: DebayerMethod::Bilinear,
+            background: None,
+            yuv_matrix: YuvMatrix::Bt601,
+            tone_mapping: None,
+            split_channels: None,
+            thermal: None,
+            segmentation: false,
+        }
+    }
+
+    #[test]
+    fn remove_row_padding() {
+        let data = [1, 2, 3, 0, 4, 5, 6, 0];
+        let pixels = pixel_bytes(&image(enc::RGB8, 1, 2, 4, &data), 3).unwrap();
+        assert_eq!(pixels.as_ref(), [1, 2, 3, 4, 5, 6]);
+        // the last row may come without the padding
+        let pixels = pixel_bytes(&image(enc::RGB8, 1, 2, 4, &data[..7]), 3).unwrap();
+        assert_eq!(pixels.as_ref(), [1, 2, 3, 4, 5, 6]);
+        // rows without padding are not copied
+        let pixels = pixel_bytes(&image(enc::RGB8, 2, 1, 6, &data), 3).unwrap();
+        assert!(matches!(pixels, Cow::Borrowed([1, 2, 3, 0, 4, 5])));
+    }
+
+    #[test]
+    fn reject_short_pixel_data() {
+        // step is shorter than the row
+        assert!(pixel_bytes(&image(enc::RGB8, 2, 1, 5, &[0; 6]), 3).is_err());
+        // data is shorter than the rows
+        assert!(pixel_bytes(&image(enc::RGB8, 1, 2, 4, &[0; 6]), 3).is_err());
+        assert!(pixel_bytes(&image(enc::RGB8, 2, 2, 6, &[0; 11]), 3).is_err());
+        let short = DepthDecoder.decode(&image(enc::TYPE_16UC1, 2, 2, 4, &[1; 7]), &options());
+        assert!(short.is_err());
+    }
+
+    #[test]
+    fn read_big_endian_values() {
+        let data = [0x01, 0x02, 0x03, 0x04, 0xff, 0xff];
+        let mut depth = image(enc::TYPE_16UC1, 2, 1, 6, &data);
+        depth.is_bigendian = true;
+        assert_eq!(
+            read_u16(&depth, &pixel_bytes(&depth, 2).unwrap()),
+            [0x0102, 0x0304]
+        );
+        match DepthDecoder.decode(&depth, &options()).unwrap() {
+            Frame::Depth(depth) => assert_eq!(depth.to_millimetres(), [0x0102, 0x0304]),
+            _ => panic!("16UC1 image is not a depth map"),
+        }
+
+        let data = [0x3f, 0xc0, 0, 0];
+        let mut depth = image(enc::TYPE_32FC1, 1, 1, 4, &data);
+        depth.is_bigendian = true;
+        assert_eq!(read_f32(&depth, depth.data), [1.5]);
+        depth.is_bigendian = false;
+        assert_ne!(read_f32(&depth, depth.data), [1.5]);
+    }
+}
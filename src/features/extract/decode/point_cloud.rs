@@ -0,0 +1,174 @@
+use byteorder::{ByteOrder, BE, LE};
+use image::Rgb;
+
+use crate::common::error::AppError;
+use crate::common::range::ValueRange;
+use crate::features::extract::tone_map::ToneMapping;
+use crate::sensor_msgs::point_field::{
+    FLOAT32, FLOAT64, INT16, INT32, INT8, UINT16, UINT32, UINT8,
+};
+use crate::sensor_msgs::{PointCloud2, PointField};
+
+use super::{DecodeOptions, Frame};
+
+/// Coordinates of the points, which are not exported by default
+const COORDINATES: [&str; 3] = ["x", "y", "z"];
+
+/// Field calculated from the coordinates if the cloud doesn't have it
+const RANGE: &str = "range";
+
+/// Decode fields of the organized `sensor_msgs/PointCloud2` as images.
+///
+/// Every point of the cloud becomes a pixel, so lidar clouds give panoramas with
+/// a row per beam. Values are rendered like float images, with the `auto` range by default.
+///
+/// # Arguments
+///
+/// * `cloud` - point cloud message
+/// * `fields` - names of the exported fields, all fields except coordinates if it's empty
+/// * `options` - decoding options with the tone mapping
+pub fn decode_point_cloud(
+    cloud: &PointCloud2,
+    fields: &[String],
+    options: &DecodeOptions,
+) -> Result<Frame, AppError> {
+    if cloud.height <= 1 {
+        return Err(AppError::InvalidPointCloud(format!(
+            "cloud with {} points is not organized",
+            cloud.width,
+        )));
+    }
+    let row_len = cloud.width as usize * cloud.point_step as usize;
+    if cloud.point_step == 0
+        || (cloud.row_step as usize) < row_len
+        || cloud.data.len() < cloud.row_step as usize * (cloud.height as usize - 1) + row_len
+    {
+        return Err(AppError::InvalidPointCloud(format!(
+            "data size {} doesn't match {}x{} points with point step {} and row step {}",
+            cloud.data.len(),
+            cloud.width,
+            cloud.height,
+            cloud.point_step,
+            cloud.row_step,
+        )));
+    }
+
+    let mut names = if fields.is_empty() {
+        cloud
+            .fields
+            .iter()
+            .map(|field| field.name)
+            .filter(|name| !COORDINATES.contains(name))
+            .collect::<Vec<&str>>()
+    } else {
+        fields.iter().map(|name| name.as_str()).collect()
+    };
+    if names.is_empty() {
+        names.push(RANGE);
+    }
+
+    let tone_mapping = options.tone_mapping.clone().unwrap_or(ToneMapping {
+        range: ValueRange::Auto,
+        fill: Rgb([0, 0, 0]),
+        log_scale: false,
+    });
+    let mut images = Vec::new();
+    for name in names {
+        let values = match cloud.fields.iter().find(|field| field.name == name) {
+            Some(field) => read_field(cloud, field)?,
+            None if name == RANGE => read_range(cloud)?,
+            None => return Err(unknown_field(cloud, name)),
+        };
+        let image = tone_mapping
+            .to_image(cloud.width, cloud.height, 1, &values)
+            .expect("number of values is checked above");
+        images.push((name.to_string(), image));
+    }
+    Ok(Frame::Split(images))
+}
+
+/// Values of the first element of the field for every point, in row-major order
+fn read_field(cloud: &PointCloud2, field: &PointField) -> Result<Vec<f32>, AppError> {
+    let size = field.size().ok_or_else(|| {
+        AppError::InvalidPointCloud(format!(
+            "field `{}` has unknown datatype {}",
+            field.name, field.datatype,
+        ))
+    })?;
+    let offset = field.offset as usize;
+    if offset + size > cloud.point_step as usize {
+        return Err(AppError::InvalidPointCloud(format!(
+            "field `{}` is out of the point with step {}",
+            field.name, cloud.point_step,
+        )));
+    }
+
+    let (row_step, point_step) = (cloud.row_step as usize, cloud.point_step as usize);
+    let values = (0..cloud.height as usize)
+        .flat_map(|row| {
+            (0..cloud.width as usize).map(move |col| row * row_step + col * point_step + offset)
+        })
+        .map(|start| {
+            read_value(
+                &cloud.data[start..start + size],
+                field.datatype,
+                cloud.is_bigendian,
+            )
+        })
+        .collect();
+    Ok(values)
+}
+
+/// Convert the field element to float
+fn read_value(bytes: &[u8], datatype: u8, is_bigendian: bool) -> f32 {
+    macro_rules! read {
+        ($read:ident) => {
+            if is_bigendian {
+                BE::$read(bytes)
+            } else {
+                LE::$read(bytes)
+            }
+        };
+    }
+    match datatype {
+        INT8 => bytes[0] as i8 as f32,
+        UINT8 => bytes[0] as f32,
+        INT16 => read!(read_i16) as f32,
+        UINT16 => read!(read_u16) as f32,
+        INT32 => read!(read_i32) as f32,
+        UINT32 => read!(read_u32) as f32,
+        FLOAT32 => read!(read_f32),
+        FLOAT64 => read!(read_f64) as f32,
+        _ => unreachable!("datatype size is checked when reading the field"),
+    }
+}
+
+/// Distance from the sensor to every point, `NaN` for invalid points
+fn read_range(cloud: &PointCloud2) -> Result<Vec<f32>, AppError> {
+    let mut coordinates = Vec::new();
+    for name in COORDINATES {
+        match cloud.fields.iter().find(|field| field.name == name) {
+            Some(field) => coordinates.push(read_field(cloud, field)?),
+            None => return Err(unknown_field(cloud, RANGE)),
+        }
+    }
+    let values = (0..coordinates[0].len())
+        .map(|i| {
+            let (x, y, z) = (coordinates[0][i], coordinates[1][i], coordinates[2][i]);
+            (x * x + y * y + z * z).sqrt()
+        })
+        .collect();
+    Ok(values)
+}
+
+fn unknown_field(cloud: &PointCloud2, name: &str) -> AppError {
+    AppError::UnknownCloudField(
+        name.to_string(),
+        cloud
+            .fields
+            .iter()
+            .map(|field| field.name)
+            .collect::<Vec<&str>>()
+            .join("`, `"),
+    )
+}
@@ -0,0 +1,87 @@
+use crate::common::error::AppError;
+use crate::sensor_msgs::Image;
+
+use super::bayer::BayerDecoder;
+use super::color::{MonoDecoder, RgbDecoder, RgbaDecoder};
+use super::cv_type::CvTypeDecoder;
+use super::depth::DepthDecoder;
+use super::flow::FlowDecoder;
+use super::label_map::LabelMapDecoder;
+use super::thermal::ThermalDecoder;
+use super::yuv::{Packed422Decoder, Planar420Decoder};
+use super::{DecodeOptions, Frame, ImageDecoder};
+
+/// Set of decoders which the extractor consults to convert images
+/// with different encodings.
+pub struct DecoderRegistry {
+    decoders: Vec<Box<dyn ImageDecoder>>,
+}
+
+impl DecoderRegistry {
+    /// Registry without any decoders
+    pub fn new() -> Self {
+        Self {
+            decoders: Vec::new(),
+        }
+    }
+
+    /// Add the decoder to the registry.
+    ///
+    /// Decoders registered later take precedence over the earlier ones
+    /// for the same encoding, so the built-in decoders can be overridden.
+    pub fn register(&mut self, decoder: Box<dyn ImageDecoder>) {
+        self.decoders.push(decoder);
+    }
+
+    /// Find the decoder for the `encoding` which accepts the `options`
+    pub fn find(&self, encoding: &str, options: &DecodeOptions) -> Option<&dyn ImageDecoder> {
+        self.decoders
+            .iter()
+            .rev()
+            .find(|decoder| decoder.supports(encoding) && decoder.accepts(options))
+            .map(|decoder| decoder.as_ref())
+    }
+
+    /// All encodings supported by the registered decoders
+    pub fn encodings(&self) -> Vec<&'static str> {
+        let mut encodings = Vec::new();
+        for decoder in &self.decoders {
+            for encoding in decoder.encodings() {
+                if !encodings.contains(encoding) {
+                    encodings.push(*encoding);
+                }
+            }
+        }
+        encodings
+    }
+
+    /// Convert pixels of the `image` with the suitable decoder
+    /// or return [AppError::UnsupportedImageEncoding]
+    pub fn decode(&self, image: &Image, options: &DecodeOptions) -> Result<Frame, AppError> {
+        match self.find(image.encoding, options) {
+            Some(decoder) => decoder.decode(image, options),
+            None => Err(AppError::UnsupportedImageEncoding(
+                image.encoding.to_string(),
+            )),
+        }
+    }
+}
+
+impl Default for DecoderRegistry {
+    /// Registry with all decoders built into the app
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry.register(Box::new(RgbDecoder));
+        registry.register(Box::new(RgbaDecoder));
+        registry.register(Box::new(MonoDecoder));
+        registry.register(Box::new(DepthDecoder));
+        registry.register(Box::new(BayerDecoder));
+        registry.register(Box::new(Packed422Decoder));
+        registry.register(Box::new(Planar420Decoder));
+        registry.register(Box::new(CvTypeDecoder));
+        registry.register(Box::new(FlowDecoder));
+        registry.register(Box::new(ThermalDecoder));
+        registry.register(Box::new(LabelMapDecoder));
+        registry
+    }
+}
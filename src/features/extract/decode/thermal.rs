@@ -0,0 +1,74 @@
+use image::{DynamicImage, ImageBuffer, Rgb};
+
+use crate::args::Palette;
+use crate::common::error::AppError;
+use crate::features::extract::palette::colorize;
+use crate::features::extract::tone_map::ToneMapping;
+use crate::sensor_msgs::{image_encodings as enc, Image};
+
+use super::{invalid_encoding, pixel_bytes, read_u16, DecodeOptions, Frame, ImageDecoder};
+
+/// Temperature of every pixel in degrees Celsius
+pub struct ThermalMap {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<f32>,
+}
+
+/// Decoder of radiometric `mono16` images from thermal cameras.
+///
+/// Images from topics which are not marked as thermal are left to the usual `mono16` decoder.
+pub struct ThermalDecoder;
+
+impl ImageDecoder for ThermalDecoder {
+    fn encodings(&self) -> &[&'static str] {
+        &[enc::MONO16]
+    }
+
+    fn accepts(&self, options: &DecodeOptions) -> bool {
+        options.thermal.is_some()
+    }
+
+    fn decode(&self, image: &Image, options: &DecodeOptions) -> Result<Frame, AppError> {
+        let calibration = match options.thermal {
+            Some(calibration) => calibration,
+            None => return Err(invalid_encoding(image)),
+        };
+
+        let data = read_u16(image, &pixel_bytes(image, 2)?)
+            .into_iter()
+            .map(|counts| (counts as f64 * calibration.gain + calibration.offset) as f32)
+            .collect::<Vec<f32>>();
+        if data.len() != image.width as usize * image.height as usize {
+            return Err(invalid_encoding(image));
+        }
+        Ok(Frame::Thermal(ThermalMap {
+            width: image.width,
+            height: image.height,
+            data,
+        }))
+    }
+}
+
+impl ThermalMap {
+    /// Render the temperature with the palette
+    ///
+    /// # Arguments
+    ///
+    /// * `palette` - colors from the coldest to the hottest
+    /// * `tone_mapping` - range of the temperature mapped to the palette
+    pub fn to_image(&self, palette: Palette, tone_mapping: &ToneMapping) -> DynamicImage {
+        let pixels = tone_mapping
+            .normalize(&self.data)
+            .into_iter()
+            .flat_map(|t| match t {
+                Some(t) => colorize(palette, t),
+                None => tone_mapping.fill.0,
+            })
+            .collect::<Vec<u8>>();
+
+        let buffer = ImageBuffer::<Rgb<u8>, _>::from_vec(self.width, self.height, pixels)
+            .expect("thermal map size is checked when decoding");
+        DynamicImage::ImageRgb8(buffer)
+    }
+}
@@ -0,0 +1,371 @@
+use image::{DynamicImage, ImageBuffer, RgbImage};
+
+use crate::args::YuvMatrix;
+use crate::common::error::AppError;
+use crate::sensor_msgs::{image_encodings as enc, Image};
+
+use super::{invalid_encoding, pixel_bytes, DecodeOptions, Frame, ImageDecoder};
+
+/// Order of the bytes in the packed 4:2:2 macropixel (two pixels)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Packed422 {
+    /// `U0 Y0 V0 Y1`
+    Uyvy,
+    /// `Y0 U0 Y1 V0`
+    Yuyv,
+}
+
+/// Layout of the planes in the YUV 4:2:0 image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Planar420 {
+    /// Y plane followed by interleaved `U V` plane
+    Nv12,
+    /// Y plane followed by interleaved `V U` plane
+    Nv21,
+    /// Y plane followed by U and V planes
+    I420,
+    /// Y plane followed by V and U planes
+    Yv12,
+}
+
+/// Convert limited range (16..235) YUV pixel to RGB
+pub fn yuv_to_rgb(y: u8, u: u8, v: u8, matrix: YuvMatrix) -> [u8; 3] {
+    let (rv, gu, gv, bu) = match matrix {
+        YuvMatrix::Bt601 => (1.596f32, 0.392f32, 0.813f32, 2.017f32),
+        YuvMatrix::Bt709 => (1.793f32, 0.213f32, 0.533f32, 2.112f32),
+    };
+    let y = 1.164f32 * (y as f32 - 16f32);
+    let u = u as f32 - 128f32;
+    let v = v as f32 - 128f32;
+    let clamp = |value: f32| value.round().clamp(0f32, 255f32) as u8;
+    [
+        clamp(y + rv * v),
+        clamp(y - gu * u - gv * v),
+        clamp(y + bu * u),
+    ]
+}
+
+/// Convert packed YUV 4:2:2 pixels into interleaved RGB pixels.
+///
+/// Every two horizontally adjacent pixels share the same chroma values.
+///
+/// # Arguments
+///
+/// * `width`, `height` - image size
+/// * `data` - packed pixels, 2 bytes per pixel
+/// * `layout` - order of bytes in the macropixel
+/// * `matrix` - YUV to RGB conversion matrix
+pub fn packed_422_to_rgb(
+    width: usize,
+    height: usize,
+    data: &[u8],
+    layout: Packed422,
+    matrix: YuvMatrix,
+) -> Vec<u8> {
+    let mut output = Vec::with_capacity(width * height * 3);
+    for row in data.chunks_exact(width * 2).take(height) {
+        for x in 0..width {
+            let macropixel = &row[(x / 2) * 4..];
+            let (y, u, v) = match layout {
+                Packed422::Uyvy => (macropixel[1 + (x % 2) * 2], macropixel[0], macropixel[2]),
+                Packed422::Yuyv => (macropixel[(x % 2) * 2], macropixel[1], macropixel[3]),
+            };
+            output.extend_from_slice(&yuv_to_rgb(y, u, v, matrix));
+        }
+    }
+    output
+}
+
+/// Convert planar YUV 4:2:0 pixels into interleaved RGB pixels.
+///
+/// Every 2x2 block of pixels shares the same chroma values.
+/// Returns `None` if `data` is too short for the specified size and layout.
+///
+/// # Arguments
+///
+/// * `width`, `height` - image size
+/// * `step` - length of the luma row in bytes, the semi-planar chroma rows
+///   have the same length rounded up to the `U V` pair and the planar chroma
+///   rows are half as long
+/// * `data` - luma plane followed by chroma planes
+/// * `layout` - layout of the chroma planes
+/// * `matrix` - YUV to RGB conversion matrix
+pub fn planar_420_to_rgb(
+    width: usize,
+    height: usize,
+    step: usize,
+    data: &[u8],
+    layout: Planar420,
+    matrix: YuvMatrix,
+) -> Option<Vec<u8>> {
+    let chroma_width = width.div_ceil(2);
+    let chroma_height = height.div_ceil(2);
+    let luma_size = step * height;
+
+    // offsets of the U and V samples relative to the end of the luma plane
+    let half_step = step.div_ceil(2);
+    let (chroma_step, u_offset, v_offset, chroma_stride) = match layout {
+        Planar420::Nv12 => (half_step * 2, 0, 1, 2),
+        Planar420::Nv21 => (half_step * 2, 1, 0, 2),
+        Planar420::I420 => (half_step, 0, half_step * chroma_height, 1),
+        Planar420::Yv12 => (half_step, half_step * chroma_height, 0, 1),
+    };
+    let chroma_row_len = chroma_width * chroma_stride;
+    if step < width || chroma_step < chroma_row_len {
+        return None;
+    }
+    let chroma_size = match layout {
+        Planar420::Nv12 | Planar420::Nv21 => chroma_step * chroma_height,
+        Planar420::I420 | Planar420::Yv12 => chroma_step * chroma_height * 2,
+    };
+    if data.len() < luma_size + chroma_size {
+        return None;
+    }
+
+    let (luma, chroma) = data.split_at(luma_size);
+    let mut output = Vec::with_capacity(width * height * 3);
+    for y in 0..height {
+        for x in 0..width {
+            let chroma_index = (y / 2) * chroma_step + (x / 2) * chroma_stride;
+            output.extend_from_slice(&yuv_to_rgb(
+                luma[y * step + x],
+                chroma[chroma_index + u_offset],
+                chroma[chroma_index + v_offset],
+                matrix,
+            ));
+        }
+    }
+    Some(output)
+}
+
+/// Decoder of packed YUV 4:2:2 images
+pub struct Packed422Decoder;
+
+/// Decoder of planar YUV 4:2:0 images
+pub struct Planar420Decoder;
+
+impl ImageDecoder for Packed422Decoder {
+    fn encodings(&self) -> &[&'static str] {
+        &[enc::YUV422, enc::UYVY, enc::YUYV, enc::YUV422_YUY2]
+    }
+
+    fn decode(&self, image: &Image, options: &DecodeOptions) -> Result<Frame, AppError> {
+        let layout = match image.encoding {
+            enc::YUV422 | enc::UYVY => Packed422::Uyvy,
+            _ => Packed422::Yuyv,
+        };
+        let (width, height) = (image.width as usize, image.height as usize);
+        // pixel pairs share the chroma, and rows are split by the width
+        if width == 0 || height == 0 || width % 2 != 0 {
+            return Err(invalid_encoding(image));
+        }
+        let pixels = pixel_bytes(image, 2)?;
+        let rgb = packed_422_to_rgb(width, height, &pixels, layout, options.yuv_matrix);
+        let buffer: RgbImage = ImageBuffer::from_vec(image.width, image.height, rgb)
+            .ok_or_else(|| invalid_encoding(image))?;
+        Ok(Frame::Image(DynamicImage::ImageRgb8(buffer)))
+    }
+}
+
+impl ImageDecoder for Planar420Decoder {
+    fn encodings(&self) -> &[&'static str] {
+        &[enc::NV12, enc::NV21, enc::I420, enc::YV12]
+    }
+
+    fn decode(&self, image: &Image, options: &DecodeOptions) -> Result<Frame, AppError> {
+        let layout = match image.encoding {
+            enc::NV12 => Planar420::Nv12,
+            enc::NV21 => Planar420::Nv21,
+            enc::I420 => Planar420::I420,
+            _ => Planar420::Yv12,
+        };
+        let rgb = planar_420_to_rgb(
+            image.width as usize,
+            image.height as usize,
+            image.step as usize,
+            image.data,
+            layout,
+            options.yuv_matrix,
+        )
+        .ok_or_else(|| invalid_encoding(image))?;
+        let buffer: RgbImage = ImageBuffer::from_vec(image.width, image.height, rgb)
+            .ok_or_else(|| invalid_encoding(image))?;
+        Ok(Frame::Image(DynamicImage::ImageRgb8(buffer)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{image, options};
+    use super::*;
+
+    fn assert_close(actual: [u8; 3], expected: [u8; 3]) {
+        assert!(
+            actual
+                .iter()
+                .zip(expected)
+                .all(|(a, e)| (*a as i32 - e as i32).abs() <= 1),
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    /// Pixels of the decoded frame as 8-bit RGB
+    fn rgb(frame: Frame) -> Vec<u8> {
+        match frame {
+            Frame::Image(image) => image.to_rgb8().into_raw(),
+            _ => panic!("frame is not an image"),
+        }
+    }
+
+    #[test]
+    fn convert_yuv_matrices() {
+        for matrix in [YuvMatrix::Bt601, YuvMatrix::Bt709] {
+            assert_eq!(yuv_to_rgb(16, 128, 128, matrix), [0, 0, 0]);
+            assert_eq!(yuv_to_rgb(235, 128, 128, matrix), [255, 255, 255]);
+        }
+        // red and green in the limited range of the standards
+        assert_close(yuv_to_rgb(81, 90, 240, YuvMatrix::Bt601), [255, 0, 0]);
+        assert_close(yuv_to_rgb(145, 54, 34, YuvMatrix::Bt601), [0, 255, 0]);
+        assert_close(yuv_to_rgb(63, 102, 240, YuvMatrix::Bt709), [255, 0, 0]);
+        assert_close(yuv_to_rgb(173, 42, 26, YuvMatrix::Bt709), [0, 255, 0]);
+        // the wrong matrix shifts the colors
+        assert!(yuv_to_rgb(81, 90, 240, YuvMatrix::Bt709)[1] > 16);
+    }
+
+    #[test]
+    fn convert_packed_422() {
+        let matrix = YuvMatrix::Bt601;
+        let black_white = [
+            yuv_to_rgb(16, 128, 128, matrix),
+            yuv_to_rgb(235, 128, 128, matrix),
+        ]
+        .concat();
+        let uyvy = packed_422_to_rgb(2, 1, &[128, 16, 128, 235], Packed422::Uyvy, matrix);
+        assert_eq!(uyvy, black_white);
+        let yuyv = packed_422_to_rgb(2, 1, &[16, 128, 235, 128], Packed422::Yuyv, matrix);
+        assert_eq!(yuyv, black_white);
+
+        // pixel pairs share the chroma
+        let data = [16, 90, 81, 240, 16, 128, 235, 128];
+        let rgb = packed_422_to_rgb(4, 1, &data, Packed422::Yuyv, matrix);
+        let red = [
+            yuv_to_rgb(16, 90, 240, matrix),
+            yuv_to_rgb(81, 90, 240, matrix),
+        ]
+        .concat();
+        assert_eq!(rgb[..6], red);
+        assert_eq!(rgb[6..], black_white);
+    }
+
+    #[test]
+    fn decode_packed_422() {
+        // rows are padded to 6 bytes
+        let data = [128, 16, 128, 235, 0, 0, 128, 235, 128, 16, 0, 0];
+        let frame = Packed422Decoder
+            .decode(&image(enc::UYVY, 2, 2, 6, &data), &options())
+            .unwrap();
+        let (black, white) = ([0; 3], [255; 3]);
+        assert_eq!(rgb(frame), [black, white, white, black].concat());
+
+        // pixel pairs share the chroma, so the width must be even
+        let odd = Packed422Decoder.decode(&image(enc::YUYV, 3, 1, 8, &[16; 8]), &options());
+        assert!(odd.is_err());
+        let short = Packed422Decoder.decode(&image(enc::YUYV, 2, 2, 4, &[16; 6]), &options());
+        assert!(short.is_err());
+    }
+
+    /// 3x3 image with distinct luma and chroma values
+    const LUMA: [[u8; 3]; 3] = [[16, 50, 90], [120, 150, 180], [200, 220, 235]];
+    const U: [[u8; 2]; 2] = [[60, 90], [150, 200]];
+    const V: [[u8; 2]; 2] = [[210, 170], [100, 40]];
+
+    fn expected_3x3(matrix: YuvMatrix) -> Vec<u8> {
+        let mut rgb = Vec::new();
+        for y in 0..3 {
+            for x in 0..3 {
+                rgb.extend(yuv_to_rgb(
+                    LUMA[y][x],
+                    U[y / 2][x / 2],
+                    V[y / 2][x / 2],
+                    matrix,
+                ));
+            }
+        }
+        rgb
+    }
+
+    /// Planes of the 3x3 image with rows padded to the `step`
+    fn planar_3x3(layout: Planar420, step: usize) -> Vec<u8> {
+        let pad = |row: &[u8], len: usize| [row, &vec![0xff; len - row.len()]].concat();
+        let mut data = LUMA
+            .iter()
+            .flat_map(|row| pad(row, step))
+            .collect::<Vec<u8>>();
+        let half_step = step.div_ceil(2);
+        for cy in 0..2 {
+            let row = match layout {
+                Planar420::Nv12 => vec![U[cy][0], V[cy][0], U[cy][1], V[cy][1]],
+                Planar420::Nv21 => vec![V[cy][0], U[cy][0], V[cy][1], U[cy][1]],
+                Planar420::I420 => U[cy].to_vec(),
+                Planar420::Yv12 => V[cy].to_vec(),
+            };
+            let len = match layout {
+                Planar420::Nv12 | Planar420::Nv21 => half_step * 2,
+                Planar420::I420 | Planar420::Yv12 => half_step,
+            };
+            data.extend(pad(&row, len));
+        }
+        for cy in 0..2 {
+            match layout {
+                Planar420::I420 => data.extend(pad(&V[cy], half_step)),
+                Planar420::Yv12 => data.extend(pad(&U[cy], half_step)),
+                _ => (),
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn convert_planar_420_with_strides() {
+        let layouts = [
+            Planar420::Nv12,
+            Planar420::Nv21,
+            Planar420::I420,
+            Planar420::Yv12,
+        ];
+        for matrix in [YuvMatrix::Bt601, YuvMatrix::Bt709] {
+            for layout in layouts {
+                // odd width with and without the padding of rows
+                for step in [3, 4, 5] {
+                    let data = planar_3x3(layout, step);
+                    assert_eq!(
+                        planar_420_to_rgb(3, 3, step, &data, layout, matrix),
+                        Some(expected_3x3(matrix)),
+                        "{:?} step {}",
+                        layout,
+                        step
+                    );
+                    let short = &data[..data.len() - 1];
+                    assert_eq!(planar_420_to_rgb(3, 3, step, short, layout, matrix), None);
+                }
+            }
+        }
+        // rows can't be shorter than the image
+        let data = planar_3x3(Planar420::Nv12, 4);
+        assert_eq!(
+            planar_420_to_rgb(3, 3, 2, &data, Planar420::Nv12, YuvMatrix::Bt601),
+            None
+        );
+    }
+
+    #[test]
+    fn decode_planar_420() {
+        let data = planar_3x3(Planar420::Nv21, 4);
+        let frame = Planar420Decoder
+            .decode(&image(enc::NV21, 3, 3, 4, &data), &options())
+            .unwrap();
+        assert_eq!(rgb(frame), expected_3x3(YuvMatrix::Bt601));
+    }
+}
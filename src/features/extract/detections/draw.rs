@@ -0,0 +1,145 @@
+use image::{DynamicImage, ImageBuffer, Pixel, Rgb, Rgba};
+
+use crate::features::extract::decode::Frame;
+use crate::vision_msgs::Detection2D;
+
+use super::font::{glyph, is_set, GLYPH_HEIGHT, GLYPH_WIDTH};
+
+/// Colors of the classes, the same class always gets the same color
+const COLORS: [[u8; 3]; 10] = [
+    [31, 119, 180],
+    [255, 127, 14],
+    [44, 160, 44],
+    [214, 39, 40],
+    [148, 103, 189],
+    [140, 86, 75],
+    [227, 119, 194],
+    [127, 127, 127],
+    [188, 189, 34],
+    [23, 190, 207],
+];
+
+/// Draw the bounding boxes with class ids and scores onto the frame.
+///
+/// Images are converted to 8-bit RGB, or RGBA if they are transparent.
+/// Depth maps, optical flow and temperature keep the real values, so nothing is drawn on them.
+///
+/// # Arguments
+///
+/// * `frame` - decoded frame, compressed images must be decoded
+/// * `detections` - detections with the same stamp as the frame
+pub fn draw_detections(frame: &mut Frame, detections: &[Detection2D]) {
+    match frame {
+        Frame::Image(image) => draw_on_image(image, detections),
+        Frame::Split(images) => {
+            for (_, image) in images {
+                draw_on_image(image, detections);
+            }
+        }
+        Frame::Depth(_) | Frame::Flow(_) | Frame::Thermal(_) | Frame::Encoded(_) => (),
+    }
+}
+
+fn draw_on_image(image: &mut DynamicImage, detections: &[Detection2D]) {
+    if image.color().has_alpha() {
+        let mut rgba = image.to_rgba8();
+        draw(&mut rgba, detections, |[r, g, b]| Rgba([r, g, b, 255]));
+        *image = DynamicImage::ImageRgba8(rgba);
+    } else {
+        let mut rgb = image.to_rgb8();
+        draw(&mut rgb, detections, Rgb);
+        *image = DynamicImage::ImageRgb8(rgb);
+    }
+}
+
+fn draw<P: Pixel<Subpixel = u8>>(
+    image: &mut ImageBuffer<P, Vec<u8>>,
+    detections: &[Detection2D],
+    pixel: impl Fn([u8; 3]) -> P,
+) {
+    // lines and labels are thicker on large images
+    let scale = (image.width().min(image.height()) as i64 / 240).clamp(1, 4);
+    for detection in detections {
+        let left = (detection.center_x - detection.size_x / 2f64).round() as i64;
+        let top = (detection.center_y - detection.size_y / 2f64).round() as i64;
+        let right = (detection.center_x + detection.size_x / 2f64).round() as i64;
+        let bottom = (detection.center_y + detection.size_y / 2f64).round() as i64;
+
+        let class_id = detection.hypothesis.as_ref().map(|h| h.class_id.as_str());
+        let color = class_color(class_id.unwrap_or_default());
+        let p = pixel(color);
+        fill_rect(image, left, top, right, top + scale, p);
+        fill_rect(image, left, bottom - scale, right, bottom, p);
+        fill_rect(image, left, top, left + scale, bottom, p);
+        fill_rect(image, right - scale, top, right, bottom, p);
+
+        let label = match &detection.hypothesis {
+            Some(hypothesis) => format!("{} {:.2}", hypothesis.class_id, hypothesis.score),
+            None => continue,
+        };
+        let label_width = (label.chars().count() as i64 * (GLYPH_WIDTH as i64 + 1) + 1) * scale;
+        let label_height = (GLYPH_HEIGHT as i64 + 2) * scale;
+        // the label is above the box, or inside it at the top of the image
+        let label_top = if top >= label_height {
+            top - label_height
+        } else {
+            top.max(0)
+        };
+        let label_left = left.max(0);
+        fill_rect(
+            image,
+            label_left,
+            label_top,
+            label_left + label_width,
+            label_top + label_height,
+            p,
+        );
+
+        let luma = 0.299 * color[0] as f32 + 0.587 * color[1] as f32 + 0.114 * color[2] as f32;
+        let text = pixel(if luma > 140f32 {
+            [0, 0, 0]
+        } else {
+            [255, 255, 255]
+        });
+        for (i, ch) in label.chars().enumerate() {
+            let rows = glyph(ch);
+            let glyph_left = label_left + (i as i64 * (GLYPH_WIDTH as i64 + 1) + 1) * scale;
+            for y in 0..GLYPH_HEIGHT {
+                for x in 0..GLYPH_WIDTH {
+                    if is_set(&rows, x, y) {
+                        let px = glyph_left + x as i64 * scale;
+                        let py = label_top + (y as i64 + 1) * scale;
+                        fill_rect(image, px, py, px + scale, py + scale, text);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Fill the rectangle from `(left, top)` to `(right, bottom)` exclusive, clipped by the image
+fn fill_rect<P: Pixel<Subpixel = u8>>(
+    image: &mut ImageBuffer<P, Vec<u8>>,
+    left: i64,
+    top: i64,
+    right: i64,
+    bottom: i64,
+    pixel: P,
+) {
+    let (width, height) = (image.width() as i64, image.height() as i64);
+    for y in top.clamp(0, height)..bottom.clamp(0, height) {
+        for x in left.clamp(0, width)..right.clamp(0, width) {
+            image.put_pixel(x as u32, y as u32, pixel);
+        }
+    }
+}
+
+/// Numeric class ids get the colors in order, other ids are hashed
+fn class_color(class_id: &str) -> [u8; 3] {
+    let index = class_id.parse::<usize>().unwrap_or_else(|_| {
+        class_id.bytes().fold(0usize, |hash, byte| {
+            hash.wrapping_mul(31).wrapping_add(byte as usize)
+        })
+    });
+    COLORS[index % COLORS.len()]
+}
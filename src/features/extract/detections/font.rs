@@ -0,0 +1,71 @@
+/// Width of the glyphs in pixels
+pub const GLYPH_WIDTH: u32 = 3;
+/// Height of the glyphs in pixels
+pub const GLYPH_HEIGHT: u32 = 5;
+
+/// Bitmaps of the 3x5 font, a row per byte with the leftmost pixel in the bit 2.
+/// Lowercase letters are drawn as uppercase.
+const GLYPHS: &[(char, [u8; 5])] = &[
+    ('0', [7, 5, 5, 5, 7]),
+    ('1', [2, 6, 2, 2, 7]),
+    ('2', [7, 1, 7, 4, 7]),
+    ('3', [7, 1, 7, 1, 7]),
+    ('4', [5, 5, 7, 1, 1]),
+    ('5', [7, 4, 7, 1, 7]),
+    ('6', [7, 4, 7, 5, 7]),
+    ('7', [7, 1, 1, 2, 2]),
+    ('8', [7, 5, 7, 5, 7]),
+    ('9', [7, 5, 7, 1, 7]),
+    ('A', [2, 5, 7, 5, 5]),
+    ('B', [6, 5, 6, 5, 6]),
+    ('C', [3, 4, 4, 4, 3]),
+    ('D', [6, 5, 5, 5, 6]),
+    ('E', [7, 4, 6, 4, 7]),
+    ('F', [7, 4, 6, 4, 4]),
+    ('G', [3, 4, 5, 5, 3]),
+    ('H', [5, 5, 7, 5, 5]),
+    ('I', [7, 2, 2, 2, 7]),
+    ('J', [1, 1, 1, 5, 2]),
+    ('K', [5, 5, 6, 5, 5]),
+    ('L', [4, 4, 4, 4, 7]),
+    ('M', [5, 7, 7, 5, 5]),
+    ('N', [6, 5, 5, 5, 5]),
+    ('O', [2, 5, 5, 5, 2]),
+    ('P', [6, 5, 6, 4, 4]),
+    ('Q', [2, 5, 5, 6, 3]),
+    ('R', [6, 5, 6, 5, 5]),
+    ('S', [3, 4, 2, 1, 6]),
+    ('T', [7, 2, 2, 2, 2]),
+    ('U', [5, 5, 5, 5, 7]),
+    ('V', [5, 5, 5, 5, 2]),
+    ('W', [5, 5, 7, 7, 5]),
+    ('X', [5, 5, 2, 5, 5]),
+    ('Y', [5, 5, 2, 2, 2]),
+    ('Z', [7, 1, 2, 4, 7]),
+    ('.', [0, 0, 0, 0, 2]),
+    (',', [0, 0, 0, 2, 4]),
+    ('-', [0, 0, 7, 0, 0]),
+    ('_', [0, 0, 0, 0, 7]),
+    (':', [0, 2, 0, 2, 0]),
+    ('/', [1, 1, 2, 4, 4]),
+    ('%', [5, 1, 2, 4, 5]),
+    (' ', [0, 0, 0, 0, 0]),
+];
+
+/// Glyph drawn for the characters missing in the font
+const UNKNOWN: [u8; 5] = [7, 1, 2, 0, 2];
+
+/// Bitmap of the character
+pub fn glyph(ch: char) -> [u8; 5] {
+    let ch = ch.to_ascii_uppercase();
+    GLYPHS
+        .iter()
+        .find(|(glyph, _)| *glyph == ch)
+        .map(|(_, rows)| *rows)
+        .unwrap_or(UNKNOWN)
+}
+
+/// Is the pixel of the glyph set?
+pub fn is_set(rows: &[u8; 5], x: u32, y: u32) -> bool {
+    rows[y as usize] & (1 << (GLYPH_WIDTH - 1 - x)) != 0
+}
@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use rosbag::record_types::{Chunk, Connection};
+use rosbag::{ChunkRecord, IndexRecord, MessageRecord, RosBag};
+
+use crate::common::de::Time;
+use crate::common::error::AppError;
+use crate::common::msg_def::MessageDefinition;
+use crate::features::extract::view::View;
+use crate::features::renderer::Renderer;
+use crate::vision_msgs::{Detection2D, Detection2DArray};
+
+mod draw;
+mod font;
+
+pub use self::draw::draw_detections;
+
+/// All detections of the topic by the stamp of the image
+#[derive(Debug, Default)]
+pub struct DetectionTopic {
+    by_stamp: HashMap<u64, Vec<Detection2D>>,
+}
+
+impl DetectionTopic {
+    /// Detections of the image with the header stamp
+    pub fn get(&self, stamp: Time) -> Option<&[Detection2D]> {
+        self.by_stamp.get(&stamp.nanos()).map(Vec::as_slice)
+    }
+
+    /// Class ids of all hypotheses of the topic, with duplicates
+    pub fn class_ids(&self) -> impl Iterator<Item = &str> {
+        self.by_stamp
+            .values()
+            .flatten()
+            .filter_map(|detection| detection.hypothesis.as_ref())
+            .map(|hypothesis| hypothesis.class_id.as_str())
+    }
+}
+
+/// Detection topic which messages are read
+struct DetectionConnection {
+    topic: String,
+    definition: MessageDefinition,
+}
+
+/// Read all messages of the detection topics before the export.
+///
+/// Detectors publish results after their input images, so the detections are
+/// recorded later than the images and can't be matched while the images are exported.
+/// Only the chunks with the detection topics are read if the bag has the index.
+///
+/// # Arguments
+///
+/// * `bag` - rosbag file
+/// * `topics` - names of the `vision_msgs/Detection2DArray` topics
+/// * `renderer` - renderer for the warnings about the topics
+pub fn load_detections(
+    bag: &RosBag,
+    topics: &[&str],
+    renderer: &Renderer,
+) -> Result<HashMap<String, Rc<DetectionTopic>>, AppError> {
+    if topics.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let mut connections = HashMap::new();
+    let mut positions = Vec::new();
+    let mut indexed = false;
+    for record in bag.index_records() {
+        match record.map_err(AppError::RosBagInvalidChunk)? {
+            IndexRecord::Connection(connection) => {
+                add_connection(connection, topics, &mut connections, renderer)
+            }
+            IndexRecord::ChunkInfo(info) => {
+                indexed = true;
+                if info
+                    .entries()
+                    .any(|entry| connections.contains_key(&entry.conn_id))
+                {
+                    positions.push(info.chunk_pos);
+                }
+            }
+            IndexRecord::IndexData(_) => (),
+        }
+    }
+
+    let mut loaded = HashMap::new();
+    let mut chunks = bag.chunk_records();
+    let mut positions = positions.into_iter();
+    loop {
+        // without the index of the chunks all of them are read
+        if indexed {
+            match positions.next() {
+                Some(position) => chunks
+                    .seek(position)
+                    .map_err(AppError::RosBagInvalidChunk)?,
+                None => break,
+            }
+        }
+        let record = match chunks.next() {
+            Some(record) => record.map_err(AppError::RosBagInvalidChunk)?,
+            None => break,
+        };
+        if let ChunkRecord::Chunk(chunk) = record {
+            read_chunk(chunk, topics, &mut connections, &mut loaded, renderer)?;
+        }
+    }
+
+    for topic in topics {
+        if !loaded.contains_key(*topic) {
+            renderer.line(View::NoMessages(topic.to_string(), false));
+        }
+    }
+    Ok(loaded
+        .into_iter()
+        .map(|(topic, detections)| (topic, Rc::new(detections)))
+        .collect())
+}
+
+fn add_connection(
+    connection: Connection,
+    topics: &[&str],
+    connections: &mut HashMap<u32, DetectionConnection>,
+    renderer: &Renderer,
+) {
+    // connections are stored both in the chunks and in the index
+    if !topics.contains(&connection.topic) || connections.contains_key(&connection.id) {
+        return;
+    }
+    if connection.tp != Detection2DArray::ros_type() {
+        renderer.line(View::IncompatibleTopicType(
+            connection.topic.to_string(),
+            connection.tp.to_string(),
+            Detection2DArray::ros_type().to_string(),
+        ));
+        return;
+    }
+    match MessageDefinition::parse(connection.tp, connection.message_definition) {
+        Ok(definition) => {
+            connections.insert(
+                connection.id,
+                DetectionConnection {
+                    topic: connection.topic.to_string(),
+                    definition,
+                },
+            );
+        }
+        Err(e) => renderer.line(View::SkippedTopic(
+            connection.topic.to_string(),
+            e.to_string(),
+        )),
+    }
+}
+
+fn read_chunk(
+    chunk: Chunk,
+    topics: &[&str],
+    connections: &mut HashMap<u32, DetectionConnection>,
+    loaded: &mut HashMap<String, DetectionTopic>,
+    renderer: &Renderer,
+) -> Result<(), AppError> {
+    for msg in chunk.messages() {
+        match msg.map_err(AppError::RosBagInvalidMessage)? {
+            MessageRecord::Connection(connection) => {
+                add_connection(connection, topics, connections, renderer)
+            }
+            MessageRecord::MessageData(data) => {
+                let connection = match connections.get(&data.conn_id) {
+                    Some(connection) => connection,
+                    None => continue,
+                };
+                // images of the malformed messages are exported without boxes
+                let array = match read_detections(&connection.definition, data.data) {
+                    Ok(array) => array,
+                    Err(cause) => {
+                        renderer.line(View::SkippedMessage(connection.topic.clone(), cause));
+                        continue;
+                    }
+                };
+                loaded
+                    .entry(connection.topic.clone())
+                    .or_default()
+                    .by_stamp
+                    .entry(array.stamp.nanos())
+                    .or_default()
+                    .extend(array.detections);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_detections(
+    definition: &MessageDefinition,
+    data: &[u8],
+) -> Result<Detection2DArray, String> {
+    let value = definition.decode(data).map_err(|e| e.to_string())?;
+    Detection2DArray::from_value(&value)
+        .map_err(|field| format!("Message has no field `{}`", field))
+}
@@ -1,16 +1,94 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
 
-use image::{ImageBuffer, RgbImage};
+use clap::ValueEnum;
+use image::Rgb;
 use regex::Regex;
 use rosbag::record_types::Connection;
 use rosbag::{ChunkRecord, MessageRecord, RosBag};
 
-use crate::common::cursor::Cursor;
+use crate::common::de::from_bytes;
 use crate::common::error::AppError;
+use crate::common::msg_def::{parse_field_path, FieldPath, MessageDefinition};
 use crate::common::naming::to_res_name;
+use crate::common::range::ValueRange;
+use crate::common::size::Size;
+use crate::features::extract::decode::{
+    decode_compressed, decode_disparity, decode_point_cloud, DecodeOptions, DecoderRegistry, Frame,
+};
+use crate::features::extract::detections::{draw_detections, load_detections, DetectionTopic};
+use crate::features::extract::labels::LabelWriter;
+use crate::features::extract::save::save;
+use crate::features::extract::segmentation::{
+    blend, class_ids, colorize, LabelPalette, OverlayImages,
+};
+use crate::features::extract::video::VideoStream;
 use crate::features::extract::view::View;
+use crate::foxglove_msgs;
 use crate::sensor_msgs;
-use crate::{args::Args, features::renderer::Renderer};
+use crate::stereo_msgs;
+use crate::{
+    args::{
+        Args, DepthFormat, DepthUnits, FlowFormat, LabelFormat, OutputFormat, SplitChannels,
+        VideoFormat, YuvMatrix,
+    },
+    features::renderer::Renderer,
+};
+
+/// Message types which contain images
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TopicKind {
+    /// `sensor_msgs/Image`
+    Image,
+    /// `sensor_msgs/CompressedImage`
+    CompressedImage,
+    /// `stereo_msgs/DisparityImage`
+    DisparityImage,
+    /// `sensor_msgs/PointCloud2`
+    PointCloud,
+    /// `foxglove_msgs/CompressedVideo`
+    CompressedVideo,
+}
+
+impl TopicKind {
+    fn from_ros_type(ros_type: &str) -> Option<Self> {
+        if ros_type == sensor_msgs::Image::ros_type() {
+            Some(Self::Image)
+        } else if ros_type == sensor_msgs::CompressedImage::ros_type() {
+            Some(Self::CompressedImage)
+        } else if ros_type == stereo_msgs::DisparityImage::ros_type() {
+            Some(Self::DisparityImage)
+        } else if ros_type == sensor_msgs::PointCloud2::ros_type() {
+            Some(Self::PointCloud)
+        } else if ros_type == foxglove_msgs::CompressedVideo::ros_type() {
+            Some(Self::CompressedVideo)
+        } else {
+            None
+        }
+    }
+
+    fn supported_ros_types() -> Vec<&'static str> {
+        vec![
+            sensor_msgs::Image::ros_type(),
+            sensor_msgs::CompressedImage::ros_type(),
+            stereo_msgs::DisparityImage::ros_type(),
+            sensor_msgs::PointCloud2::ros_type(),
+            foxglove_msgs::CompressedVideo::ros_type(),
+        ]
+    }
+}
+
+/// Export states by connection id and path to the nested image field
+pub type TopicStates = BTreeMap<(u32, Option<FieldPath>), TopicState>;
+
+/// Field of the custom message which contains the image
+#[derive(Debug)]
+struct NestedField {
+    /// Definition of the connection type
+    definition: Rc<MessageDefinition>,
+    /// Path to the image field
+    path: FieldPath,
+}
 
 #[derive(Debug)]
 pub struct TopicState {
@@ -18,41 +96,98 @@ pub struct TopicState {
     counter: u32,
     /// Number of all extracted frames
     pub extracted: u32,
-    /// Topic name
+    /// Topic name, with the path to the image field for nested images
     pub name: String,
+    /// Topic name
+    topic: String,
     /// Topic files base name
     res_name: String,
+    /// Type of the topic images
+    kind: TopicKind,
+    /// Image field of the custom message
+    nested: Option<NestedField>,
     /// Is export process done?
     done: bool,
+    /// Are color channels of the topic mixed up?
+    invert_channels: bool,
+    /// Is the topic from thermal camera?
+    thermal: bool,
+    /// Video stream which is written until the end of the export
+    video: Option<VideoStream>,
+    /// Detections drawn onto the images or exported as labels
+    detections: Option<Rc<DetectionTopic>>,
+    /// Writer of the labels made from the detections, which are not drawn then
+    labels: Option<Rc<LabelWriter>>,
+    /// Colors of the classes, if the topic contains segmentation masks
+    segmentation: Option<Rc<LabelPalette>>,
+    /// Images which the segmentation masks are blended onto
+    overlay: Option<Rc<OverlayImages>>,
 }
 
 impl TopicState {
-    fn new(name: String) -> Self {
+    /// # Arguments
+    ///
+    /// * `topic` - topic name
+    /// * `kind` - message type of the topic
+    /// * `nested` - image field, if the image is nested in a custom message
+    /// * `topics` - topics from the app arguments with the options of the topic
+    fn new(topic: String, kind: TopicKind, nested: Option<NestedField>, topics: &Topics) -> Self {
+        let name = match &nested {
+            Some(nested) => format!("{}:{}", topic, nested.path),
+            None => topic.clone(),
+        };
+        let detections = topics.detections(&topic, nested.as_ref().map(|nested| &nested.path));
         TopicState {
             counter: 0,
             extracted: 0,
             res_name: to_res_name(&name),
             name,
+            kind,
+            nested,
             done: false,
+            invert_channels: topics.is_inverted(&topic),
+            thermal: topics.is_thermal(&topic),
+            video: None,
+            detections,
+            labels: topics.labels.clone(),
+            segmentation: topics
+                .is_segmentation(&topic)
+                .then(|| topics.palette.clone()),
+            overlay: topics.overlay(&topic),
+            topic,
         }
     }
 }
 
 #[derive(Debug)]
 struct TopicName<'a> {
+    /// Topic name as it is specified in the app arguments
+    full: &'a str,
     plain: &'a str,
     pattern: Option<Regex>,
+    /// Path to the image field of the custom message, e.g. `/bundle:left.image`
+    field: Option<FieldPath>,
 }
 
 impl<'a> TopicName<'a> {
-    fn new(name: &'a str, regex: bool) -> Result<Self, AppError> {
+    fn new(full: &'a str, regex: bool) -> Result<Self, AppError> {
+        // topic names can't contain `:`, so it separates the field path
+        let (name, field) = match full.rsplit_once(':') {
+            Some((name, path)) => match parse_field_path(path) {
+                Ok(path) => (name, Some(path)),
+                Err(_) => (full, None),
+            },
+            None => (full, None),
+        };
         Ok(Self {
+            full,
             plain: name,
             pattern: if regex {
                 Some(Regex::new(name).map_err(|_| AppError::ArgsInvalidRegex(name.to_string()))?)
             } else {
                 None
             },
+            field,
         })
     }
 
@@ -65,76 +200,256 @@ impl<'a> TopicName<'a> {
     }
 }
 
-pub fn extract(args: Args) {
+/// Topics specified in the app arguments
+struct Topics<'a> {
+    /// Topics to export images from
+    requested: Vec<TopicName<'a>>,
+    /// Color channels are mixed up in all topics
+    invert_all: bool,
+    /// Topics with mixed up color channels
+    inverted: Vec<TopicName<'a>>,
+    /// Topics from thermal cameras
+    thermal: Vec<TopicName<'a>>,
+    /// Topics with the detections drawn onto the images
+    detections: Vec<(TopicName<'a>, Rc<DetectionTopic>)>,
+    /// Writer of the labels of all exported images
+    labels: Option<Rc<LabelWriter>>,
+    /// Topics with segmentation masks
+    segmentation: Vec<TopicName<'a>>,
+    /// Colors of the segmentation classes
+    palette: Rc<LabelPalette>,
+    /// Topics with segmentation masks blended onto the images
+    overlays: Vec<(TopicName<'a>, Rc<OverlayImages>)>,
+    /// Image topics which the masks are blended onto
+    overlay_images: Vec<Rc<OverlayImages>>,
+}
+
+impl<'a> Topics<'a> {
+    /// # Arguments
+    ///
+    /// * `args` - app arguments with the topic names
+    /// * `detections` - detection topics read from the bag by name
+    /// * `labels` - writer of the labels, if the detections are exported as labels
+    /// * `palette` - colors of the segmentation classes
+    fn new(
+        args: &'a Args,
+        detections: &HashMap<String, Rc<DetectionTopic>>,
+        labels: Option<Rc<LabelWriter>>,
+        palette: Rc<LabelPalette>,
+    ) -> Result<Self, AppError> {
+        let parse = |names: &'a [String]| {
+            names
+                .iter()
+                .map(|name| TopicName::new(name, args.regex))
+                .collect::<Result<Vec<TopicName>, AppError>>()
+        };
+        let inverted = parse(&args.invert_channels)?;
+
+        // masks of several topics may be blended onto the same images
+        let mut overlay_images: Vec<Rc<OverlayImages>> = Vec::new();
+        let mut overlays = Vec::new();
+        for pair in &args.seg_overlay {
+            let images = match overlay_images
+                .iter()
+                .find(|images| images.topic == pair.paired)
+            {
+                Some(images) => images.clone(),
+                None => {
+                    let invert_channels = args.invert_all_channels
+                        || inverted.iter().any(|topic| topic.matches(&pair.paired));
+                    let images = Rc::new(OverlayImages::new(pair.paired.clone(), invert_channels));
+                    overlay_images.push(images.clone());
+                    images
+                }
+            };
+            overlays.push((TopicName::new(&pair.topic, args.regex)?, images));
+        }
+
+        Ok(Self {
+            requested: parse(&args.topics)?,
+            invert_all: args.invert_all_channels,
+            inverted,
+            thermal: parse(&args.thermal)?,
+            detections: args
+                .detections
+                .iter()
+                // topics without messages are reported when the detections are read
+                .filter_map(|pair| Some((pair, detections.get(&pair.paired)?.clone())))
+                .map(|(pair, topic)| Ok((TopicName::new(&pair.topic, args.regex)?, topic)))
+                .collect::<Result<Vec<(TopicName, Rc<DetectionTopic>)>, AppError>>()?,
+            labels,
+            segmentation: parse(&args.segmentation)?,
+            palette,
+            overlays,
+            overlay_images,
+        })
+    }
+
+    fn is_inverted(&self, name: &str) -> bool {
+        self.invert_all || self.inverted.iter().any(|topic| topic.matches(name))
+    }
+
+    fn is_thermal(&self, name: &str) -> bool {
+        self.thermal.iter().any(|topic| topic.matches(name))
+    }
+
+    /// Masks blended onto the images are segmentation masks too
+    fn is_segmentation(&self, name: &str) -> bool {
+        self.segmentation.iter().any(|topic| topic.matches(name))
+            || self.overlays.iter().any(|(topic, _)| topic.matches(name))
+    }
+
+    /// Images which the masks of the topic are blended onto
+    fn overlay(&self, name: &str) -> Option<Rc<OverlayImages>> {
+        self.overlays
+            .iter()
+            .find(|(topic, _)| topic.matches(name))
+            .map(|(_, images)| images.clone())
+    }
+
+    /// Detections paired with the image topic or its nested image field
+    fn detections(&self, name: &str, field: Option<&FieldPath>) -> Option<Rc<DetectionTopic>> {
+        self.detections
+            .iter()
+            .find(|(topic, _)| topic.matches(name) && topic.field.as_ref() == field)
+            .map(|(_, detections)| detections.clone())
+    }
+}
+
+/// Export images from the bag, converting them with the `decoders`
+pub fn extract(args: Args, decoders: DecoderRegistry) {
     let renderer = Renderer();
     renderer.new_line();
 
-    if let Err(e) = extract_internal(args, &renderer) {
+    if let Err(e) = extract_internal(args, &decoders, &renderer) {
         renderer.line(View::Error(e.to_string()))
     }
 }
 
-fn extract_internal(args: Args, renderer: &Renderer) -> Result<(), AppError> {
-    validate_args(&args, &renderer)?;
+/// Print all encodings supported by the `decoders`
+pub fn list_encodings(decoders: &DecoderRegistry) {
+    let renderer = Renderer();
+    renderer.new_line();
+    let encodings = decoders
+        .encodings()
+        .iter()
+        .map(|encoding| encoding.to_string())
+        .collect();
+    renderer.line(View::SupportedEncodings(encodings));
+}
+
+fn extract_internal(
+    args: Args,
+    decoders: &DecoderRegistry,
+    renderer: &Renderer,
+) -> Result<(), AppError> {
+    let palette = match &args.seg_palette {
+        Some(path) => LabelPalette::load(path)?,
+        None => LabelPalette::default(),
+    };
+    validate_args(&args, &palette, renderer)?;
 
     let bag = RosBag::new(&args.path_to_bag).map_err(|e| AppError::RosBagOpen(e.to_string()))?;
 
-    let requested_topics = args
-        .topics
+    let detection_topics = args
+        .detections
         .iter()
-        .map(|name| TopicName::new(name, args.regex))
-        .collect::<Result<Vec<TopicName>, AppError>>()?;
+        .map(|pair| pair.paired.as_str())
+        .collect::<Vec<&str>>();
+    let detections = load_detections(&bag, &detection_topics, renderer)?;
+
+    let labels = args.labels.map(|format| {
+        let topics = detections
+            .values()
+            .map(Rc::as_ref)
+            .collect::<Vec<&DetectionTopic>>();
+        Rc::new(LabelWriter::new(format, &topics))
+    });
 
-    let mut states: BTreeMap<u32, TopicState> = BTreeMap::new();
+    let topics = Topics::new(&args, &detections, labels.clone(), Rc::new(palette))?;
+    let mut states: TopicStates = BTreeMap::new();
+    let exported = export_messages(&bag, &args, &mut states, &topics, decoders, renderer);
+
+    // Video streams and labels are finalized even if the export failed,
+    // so the frames exported before the error are usable
+    let finished = finish_outputs(&mut states, labels.as_deref(), &args.output_dir);
+    exported?;
+    finished?;
+
+    renderer.render(&states, false);
+    check_for_empty_topics(&states, &topics.requested, args.regex, renderer);
+    renderer.line(View::Done);
+    Ok(())
+}
+
+fn export_messages(
+    bag: &RosBag,
+    args: &Args,
+    states: &mut TopicStates,
+    topics: &Topics,
+    decoders: &DecoderRegistry,
+    renderer: &Renderer,
+) -> Result<(), AppError> {
     let mut start_time: u64 = 0;
 
     for record in bag.chunk_records() {
         // Termination criteria for the export process
         let is_all_finished = states.iter().all(|(_, v)| v.done);
-        let all_requested_topics_are_found = states.len() == requested_topics.len() && !args.regex;
+        let all_requested_topics_are_found = states.len() == topics.requested.len() && !args.regex;
         if is_all_finished && all_requested_topics_are_found {
             break;
         }
 
         let record = record.map_err(AppError::RosBagInvalidChunk)?;
-        match record {
-            ChunkRecord::Chunk(chunk) => {
-                for msg in chunk.messages() {
-                    let msg = msg.map_err(AppError::RosBagInvalidMessage)?;
-                    process_message(
-                        msg,
-                        &args,
-                        &mut states,
-                        &requested_topics,
-                        &mut start_time,
-                        &renderer,
-                    )?;
-                }
+        if let ChunkRecord::Chunk(chunk) = record {
+            for msg in chunk.messages() {
+                let msg = msg.map_err(AppError::RosBagInvalidMessage)?;
+                process_message(
+                    msg,
+                    args,
+                    states,
+                    topics,
+                    decoders,
+                    &mut start_time,
+                    renderer,
+                )?;
             }
-            _ => (),
         }
     }
-
-    renderer.render(&states, false);
-    check_for_empty_topics(&states, &requested_topics, args.regex, &renderer);
-    renderer.line(View::Done);
     Ok(())
 }
 
+/// Finish all open video streams and the labels, returns the first error
+fn finish_outputs(
+    states: &mut TopicStates,
+    labels: Option<&LabelWriter>,
+    output_dir: &str,
+) -> Result<(), AppError> {
+    let mut result = Ok(());
+    for state in states.values_mut() {
+        if let Some(video) = state.video.take() {
+            result = result.and(video.finish());
+        }
+    }
+    if let Some(labels) = labels {
+        result = result.and(labels.finish(output_dir));
+    }
+    result
+}
+
 fn process_message(
     msg: MessageRecord,
     args: &Args,
-    states: &mut BTreeMap<u32, TopicState>,
-    requested_topics: &[TopicName],
+    states: &mut TopicStates,
+    topics: &Topics,
+    decoders: &DecoderRegistry,
     start_time: &mut u64,
     renderer: &Renderer,
 ) -> Result<(), AppError> {
     match msg {
         MessageRecord::Connection(connection) => {
-            let is_requested_topic =
-                |another: &str| requested_topics.iter().any(|topic| topic.matches(another));
-            process_connection(connection, states, &renderer, is_requested_topic);
-            renderer.render(&states, true);
+            process_connection(connection, states, renderer, topics);
+            renderer.render(states, true);
         }
         MessageRecord::MessageData(data) => {
             // Use first message time as start time
@@ -142,105 +457,355 @@ fn process_message(
                 *start_time = data.time;
             }
             // Process message only if the data message was preceded by a connection message
-            // Reading a message with connection will create an entry in states.
-            if let Some(state) = states.get_mut(&data.conn_id) {
-                let elapsed_time_sec = (data.time - *start_time) as f64 / 1_000_000_000_f64;
-
-                // Export images only after specified start time
-                if elapsed_time_sec < args.start {
-                    return Ok(());
+            // Reading a message with connection will create entries in states.
+            let keys = states
+                .range((data.conn_id, None)..)
+                .take_while(|((conn_id, _), _)| *conn_id == data.conn_id)
+                .map(|(key, _)| key.clone())
+                .collect::<Vec<(u32, Option<FieldPath>)>>();
+            let elapsed_time_sec = (data.time - *start_time) as f64 / 1_000_000_000_f64;
+            // Images are queued for the masks only within the export time
+            // and until all masks blended onto them are exported
+            let in_time = elapsed_time_sec >= args.start
+                && args.end.map_or(true, |end| elapsed_time_sec <= end);
+            for images in &topics.overlay_images {
+                if !in_time || is_overlay_finished(args, states, images) {
+                    continue;
                 }
-
-                // Export images only before specified end time
-                if let Some(end_time) = args.end {
-                    if elapsed_time_sec > end_time {
-                        state.done = true;
-                        return Ok(());
-                    }
+                // malformed images are skipped, and so are their masks
+                if let Err(e) = images.push(data.conn_id, data.data) {
+                    renderer.line(View::SkippedMessage(images.topic.clone(), e.to_string()));
                 }
+            }
+            for key in keys {
+                let state = states.get_mut(&key).expect("key is taken from states");
+                if process_topic_message(
+                    args,
+                    state,
+                    decoders,
+                    elapsed_time_sec,
+                    data.data,
+                    renderer,
+                )? {
+                    renderer.render(states, true);
+                }
+            }
+        }
+    }
+    Ok(())
+}
 
-                state.counter += 1;
+/// Check if all found mask topics blended onto the images are exported
+fn is_overlay_finished(args: &Args, states: &TopicStates, images: &Rc<OverlayImages>) -> bool {
+    let mut masks = states
+        .values()
+        .filter(|state| {
+            state
+                .overlay
+                .as_ref()
+                .is_some_and(|overlay| Rc::ptr_eq(overlay, images))
+        })
+        .peekable();
+    masks.peek().is_some()
+        && masks
+            .all(|state| state.done || args.number.is_some_and(|number| state.extracted >= number))
+}
 
-                // Export no more than `args.number` images
-                match (args.number, state.extracted) {
-                    (Some(number), extracted) if extracted >= number => {
-                        state.done = true;
-                        return Ok(());
-                    }
-                    _ => (),
-                }
+/// Export the image from the message if it satisfies the export criteria.
+/// Returns `true` if the image is exported.
+fn process_topic_message(
+    args: &Args,
+    state: &mut TopicState,
+    decoders: &DecoderRegistry,
+    elapsed_time_sec: f64,
+    data: &[u8],
+    renderer: &Renderer,
+) -> Result<bool, AppError> {
+    // Export images only after specified start time
+    if elapsed_time_sec < args.start {
+        return Ok(false);
+    }
 
-                // Export images not in a row, but through step
-                if (state.counter - 1) % args.step != 0 {
-                    return Ok(());
-                }
+    // Export images only before specified end time
+    if let Some(end_time) = args.end {
+        if elapsed_time_sec > end_time {
+            state.done = true;
+            return Ok(false);
+        }
+    }
 
-                // renderer.line(View::Info(format!("time {}\n", elapsed_time_sec)));
-                process_image(&args, state, &data.data)?;
+    // Export no more than `args.number` images
+    match (args.number, state.extracted) {
+        (Some(number), extracted) if extracted >= number => {
+            state.done = true;
+            return Ok(false);
+        }
+        _ => (),
+    }
 
-                renderer.render(&states, true);
+    // Messages without the nested image (e.g. with shorter arrays) are skipped,
+    // as well as malformed messages
+    let data = match &state.nested {
+        Some(nested) => match nested.definition.locate(&nested.path, data) {
+            Ok(Some(data)) => data,
+            Ok(None) => return Ok(false),
+            Err(e) => {
+                renderer.line(View::SkippedMessage(state.name.clone(), e.to_string()));
+                return Ok(false);
             }
-        }
+        },
+        None => data,
+    };
+
+    state.counter += 1;
+
+    // Video packets depend on the previous ones, so they can't be skipped
+    if state.kind == TopicKind::CompressedVideo {
+        return process_video(args, state, data, renderer);
     }
-    Ok(())
+
+    // Export images not in a row, but through step
+    if (state.counter - 1) % args.step != 0 {
+        return Ok(false);
+    }
+
+    process_image(args, state, decoders, data)
 }
 
-fn process_connection<F>(
+fn process_connection(
     connection: Connection,
-    states: &mut BTreeMap<u32, TopicState>,
+    states: &mut TopicStates,
     renderer: &Renderer,
-    is_requested_topic: F,
-) where
-    F: FnOnce(&str) -> bool,
-{
+    topics: &Topics,
+) {
     let conn_id = connection.id;
     let key = connection.topic;
-    let is_desired_type = connection.tp == sensor_msgs::Image::ros_type();
+    let requested = topics
+        .requested
+        .iter()
+        .filter(|topic| topic.matches(key))
+        .collect::<Vec<&TopicName>>();
+    let new_state = |kind: TopicKind, nested: Option<NestedField>| {
+        TopicState::new(key.to_string(), kind, nested, topics)
+    };
 
-    match (is_requested_topic(key), is_desired_type) {
-        (true, true) => {
-            states.insert(conn_id, TopicState::new(key.to_string()));
+    // Images of the topic are queued for the segmentation masks
+    for images in topics
+        .overlay_images
+        .iter()
+        .filter(|images| images.topic == key)
+    {
+        match TopicKind::from_ros_type(connection.tp) {
+            Some(TopicKind::Image) => images.add_connection(conn_id, false),
+            Some(TopicKind::CompressedImage) => images.add_connection(conn_id, true),
+            _ => renderer.line(View::IncompatibleTopicType(
+                key.to_string(),
+                connection.tp.to_string(),
+                [
+                    sensor_msgs::Image::ros_type(),
+                    sensor_msgs::CompressedImage::ros_type(),
+                ]
+                .join("`, `"),
+            )),
         }
-        (true, false) => renderer.line(View::IncompatibleTopicType(
-            key.to_string(),
-            connection.tp.to_string(),
-            sensor_msgs::Image::ros_type().to_string(),
-        )),
-        _ => (),
     }
-}
 
-fn process_image(args: &Args, state: &mut TopicState, data: &[u8]) -> Result<(), AppError> {
-    let image = sensor_msgs::Image::from_reader(&mut Cursor::new(data))?;
+    // The whole message is an image
+    if requested.iter().any(|topic| topic.field.is_none()) {
+        match TopicKind::from_ros_type(connection.tp) {
+            Some(kind) => {
+                states.insert((conn_id, None), new_state(kind, None));
+            }
+            None => {
+                renderer.line(View::IncompatibleTopicType(
+                    key.to_string(),
+                    connection.tp.to_string(),
+                    TopicKind::supported_ros_types().join("`, `"),
+                ));
+                // Maybe the message contains images in its fields
+                let fields = MessageDefinition::parse(connection.tp, connection.message_definition)
+                    .map(|definition| definition.find_fields(&TopicKind::supported_ros_types()))
+                    .unwrap_or_default();
+                if !fields.is_empty() {
+                    renderer.line(View::NestedImages(
+                        fields
+                            .iter()
+                            .map(|path| format!("{}:{}", key, path))
+                            .collect(),
+                    ));
+                }
+            }
+        }
+    }
 
-    let mut buffer: RgbImage =
-        ImageBuffer::from_vec(image.width, image.height, image.data.to_vec())
-            .ok_or(AppError::InvalidImageEncoding(image.encoding.to_string()))?;
+    // The message contains images in its fields
+    let mut paths = requested
+        .iter()
+        .filter_map(|topic| topic.field.as_ref())
+        .collect::<Vec<&FieldPath>>();
+    paths.sort();
+    paths.dedup();
+    if paths.is_empty() {
+        return;
+    }
+    let definition = match MessageDefinition::parse(connection.tp, connection.message_definition) {
+        Ok(definition) => Rc::new(definition),
+        Err(e) => {
+            renderer.line(View::SkippedTopic(key.to_string(), e.to_string()));
+            return;
+        }
+    };
+    for path in paths {
+        let name = format!("{}:{}", key, path);
+        let field_type = match definition.field_type(path) {
+            Ok(field_type) => field_type,
+            Err(e) => {
+                renderer.line(View::SkippedTopic(name, e.to_string()));
+                continue;
+            }
+        };
+        match TopicKind::from_ros_type(field_type) {
+            Some(kind) => {
+                let nested = NestedField {
+                    definition: definition.clone(),
+                    path: path.clone(),
+                };
+                states.insert((conn_id, Some(path.clone())), new_state(kind, Some(nested)));
+            }
+            None => renderer.line(View::IncompatibleTopicType(
+                name,
+                field_type.to_string(),
+                TopicKind::supported_ros_types().join("`, `"),
+            )),
+        }
+    }
+}
 
-    // for cases when cv_bridge shits yourself and mix up color channels
-    if args.invert_channels {
-        for p in buffer.pixels_mut() {
-            let [r, g, b] = p.0;
-            p.0 = [b, g, r];
+/// Decode and save the image.
+/// Returns `true` if the image is exported.
+fn process_image(
+    args: &Args,
+    state: &mut TopicState,
+    decoders: &DecoderRegistry,
+    data: &[u8],
+) -> Result<bool, AppError> {
+    let options = DecodeOptions::new(
+        args,
+        state.invert_channels,
+        state.thermal,
+        state.segmentation.is_some(),
+    );
+    let (mut frame, stamp) = match state.kind {
+        TopicKind::Image => {
+            let image = from_bytes::<sensor_msgs::Image>(data)?;
+            (decoders.decode(&image, &options)?, image.header.stamp)
+        }
+        TopicKind::CompressedImage => {
+            let image = from_bytes::<sensor_msgs::CompressedImage>(data)?;
+            // compressed images are decoded only if they must be processed,
+            // detections exported as labels keep the original images
+            let reencode = args.format.is_some()
+                || args.resize.is_some()
+                || (state.detections.is_some() && state.labels.is_none())
+                || state.segmentation.is_some();
+            (
+                decode_compressed(&image, &options, reencode)?,
+                image.header.stamp,
+            )
         }
+        TopicKind::DisparityImage => {
+            let disparity = from_bytes::<stereo_msgs::DisparityImage>(data)?;
+            (
+                decode_disparity(&disparity, &options, args.disparity_depth)?,
+                disparity.header.stamp,
+            )
+        }
+        TopicKind::PointCloud => {
+            let cloud = from_bytes::<sensor_msgs::PointCloud2>(data)?;
+            (
+                decode_point_cloud(&cloud, &args.cloud_fields, &options)?,
+                cloud.header.stamp,
+            )
+        }
+        // video packets are extracted as a stream, not decoded into images
+        TopicKind::CompressedVideo => unreachable!("video packets are not decoded"),
+    };
+
+    // masks are colorized, or blended onto the images with the same stamp
+    if let Some(palette) = &state.segmentation {
+        let ids = class_ids(&frame, &state.name)?;
+        frame = match &state.overlay {
+            Some(overlay) => match overlay.take(stamp.nanos(), decoders, args)? {
+                Some(image) => Frame::Image(blend(&image, &ids, palette, args.seg_alpha)),
+                None => return Ok(false),
+            },
+            None => Frame::Image(colorize(&ids, palette)),
+        };
+    }
+
+    let detections = state.detections.as_ref().map(|topic| topic.get(stamp));
+    match (&state.labels, detections) {
+        // frames without detections would become negative samples of the dataset
+        (Some(_), Some(None)) => return Ok(false),
+        (None, Some(Some(detections))) => draw_detections(&mut frame, detections),
+        _ => (),
     }
 
-    let save_path = format!(
-        "{}/{}_{}.png",
+    let base_path = format!(
+        "{}/{}_{}",
         args.output_dir,
         state.res_name,
         state.extracted + 1,
     );
-
-    buffer
-        .save_with_format(&save_path, image::ImageFormat::Png)
-        .map_err(|e| AppError::CannotSave(save_path, e.to_string()))?;
+    let images = save(&frame, &base_path, args)?;
+    if let Some(labels) = &state.labels {
+        labels.write(&frame, &images, stamp, detections.flatten())?;
+    }
 
     state.extracted += 1;
-    Ok(())
+    Ok(true)
+}
+
+/// Append the packet to the video stream of the topic.
+/// Returns `true` if the packet is written.
+///
+/// The stream written so far is kept if its packets can't be extracted
+/// (e.g. the codec changed), and the rest of the topic is skipped.
+fn process_video(
+    args: &Args,
+    state: &mut TopicState,
+    data: &[u8],
+    renderer: &Renderer,
+) -> Result<bool, AppError> {
+    if state.done {
+        return Ok(false);
+    }
+    let packet = from_bytes::<foxglove_msgs::CompressedVideo>(data)?;
+    let pushed = match &mut state.video {
+        Some(video) => video.push(&packet),
+        None => VideoStream::new(
+            packet.format,
+            &format!("{}/{}", args.output_dir, state.res_name),
+            args.video_format,
+        )
+        .and_then(|video| state.video.insert(video).push(&packet)),
+    };
+    match pushed {
+        Ok(true) => {
+            state.extracted += 1;
+            Ok(true)
+        }
+        Ok(false) => Ok(false),
+        Err(e @ (AppError::UnsupportedVideoFormat(_) | AppError::InvalidVideoStream(_, _))) => {
+            renderer.line(View::SkippedTopic(state.name.clone(), e.to_string()));
+            state.done = true;
+            Ok(false)
+        }
+        Err(e) => Err(e),
+    }
 }
 
-fn validate_args(args: &Args, renderer: &Renderer) -> Result<(), AppError> {
+fn validate_args(args: &Args, palette: &LabelPalette, renderer: &Renderer) -> Result<(), AppError> {
     let mut lines: Vec<String> = Vec::new();
     lines.push(format!("input rosbag file: {}", args.path_to_bag));
     lines.push(format!("output dir: {}", args.output_dir));
@@ -259,9 +824,7 @@ fn validate_args(args: &Args, renderer: &Renderer) -> Result<(), AppError> {
         (start, Some(end)) if end <= start => return Err(AppError::ArgsEndBeforeStart(start, end)),
 
         // default start time is 0
-        (start, Some(end)) if start == 0f64 => {
-            lines.push(format!("export from bag start until the {:.} sec", end))
-        }
+        (0f64, Some(end)) => lines.push(format!("export from bag start until the {:.} sec", end)),
 
         // non-default start and end time
         (start, Some(end)) => lines.push(format!(
@@ -270,7 +833,7 @@ fn validate_args(args: &Args, renderer: &Renderer) -> Result<(), AppError> {
         )),
 
         // default start and end time
-        (start, None) if start == 0f64 => lines.push(format!("export from start until the end")),
+        (0f64, None) => lines.push("export from start until the end".to_string()),
 
         // non-default start time
         (start, None) => lines.push(format!("export from {:.} sec until the end", start)),
@@ -284,18 +847,16 @@ fn validate_args(args: &Args, renderer: &Renderer) -> Result<(), AppError> {
         (Some(number), _) if number < 1 => return Err(AppError::ArgsNegativeNumber(number)),
 
         // frames number and step are not specified
-        (None, step) if step == 1 => lines.push("export every frame".to_string()),
+        (None, 1) => lines.push("export every frame".to_string()),
 
         // frames number is not specified, step is specified
         (None, step) => lines.push(format!("export every {}-th frame", step)),
 
         // frames number is specified, step is not specified
-        (Some(number), _) if number == 1 => lines.push(format!("export only one frame per topic")),
+        (Some(1), _) => lines.push("export only one frame per topic".to_string()),
 
         // frames number and step are specified
-        (Some(number), step) if step == 1 => {
-            lines.push(format!("export {} frames per topic", number))
-        }
+        (Some(number), 1) => lines.push(format!("export {} frames per topic", number)),
 
         // frames number and step are specified
         (Some(number), step) => lines.push(format!(
@@ -304,37 +865,226 @@ fn validate_args(args: &Args, renderer: &Renderer) -> Result<(), AppError> {
         )),
     }
 
-    if args.invert_channels {
-        lines.push("invert color channels (RGB8 to BGR8 and vice-versa)".to_string());
+    if args.invert_all_channels {
+        lines
+            .push("invert color channels (RGB8 to BGR8 and vice-versa) for all topics".to_string());
+    } else if !args.invert_channels.is_empty() {
+        lines.push(format!(
+            "invert color channels (RGB8 to BGR8 and vice-versa) for topics: {}",
+            args.invert_channels.join(", "),
+        ));
     }
 
     if args.regex {
         lines.push("search topics with regex".to_string())
     }
 
+    if let Some(Rgb([r, g, b])) = args.background {
+        lines.push(format!(
+            "flatten transparent images onto #{:02x}{:02x}{:02x}",
+            r, g, b,
+        ));
+    }
+
+    if args.yuv_matrix == YuvMatrix::Bt709 {
+        lines.push("convert YUV images with BT.709 color matrix".to_string());
+    }
+
+    match (args.range, args.log_scale) {
+        (Some(ValueRange::Fixed(min, max)), log_scale) => lines.push(format!(
+            "render float and depth images with values from {} to {}{}",
+            min,
+            max,
+            if log_scale { " in log scale" } else { "" },
+        )),
+        (Some(ValueRange::Percentile(low, high)), log_scale) => lines.push(format!(
+            "render float and depth images with values from {}-th to {}-th percentile{}",
+            low,
+            high,
+            if log_scale { " in log scale" } else { "" },
+        )),
+        (Some(ValueRange::Auto), log_scale) | (None, log_scale @ true) => lines.push(format!(
+            "render float and depth images with values from min to max{}",
+            if log_scale { " in log scale" } else { "" },
+        )),
+        (None, false) if args.nan_color.is_some() => {
+            lines.push("render float and depth images with values from min to max".to_string())
+        }
+        (None, false) => (),
+    }
+
+    match args.split_channels {
+        Some(SplitChannels::Gray) => {
+            lines.push("export channels of multi-channel images as grayscale images".to_string())
+        }
+        Some(SplitChannels::Rgb) => {
+            lines.push("export channel triplets of multi-channel images as RGB images".to_string())
+        }
+        None => (),
+    }
+
+    match (args.depth_format, args.depth_units) {
+        (DepthFormat::Png16, Some(DepthUnits::M)) => return Err(AppError::ArgsDepthUnits),
+        (DepthFormat::Png16, _) => (),
+        (DepthFormat::Pfm, Some(DepthUnits::Mm)) => {
+            lines.push("export depth as float maps in millimetres".to_string())
+        }
+        (DepthFormat::Pfm, _) => lines.push("export depth as float maps in metres".to_string()),
+    }
+
+    if args.disparity_depth {
+        lines.push("export disparity images as depth".to_string());
+    }
+
+    match args.format {
+        Some(OutputFormat::Jpeg) => lines.push(format!(
+            "save images as JPEG with quality {}",
+            args.jpeg_quality,
+        )),
+        Some(OutputFormat::Png) => lines.push("save images as PNG".to_string()),
+        None => (),
+    }
+    match args.resize {
+        Some(Size {
+            width: Some(width),
+            height: Some(height),
+        }) => lines.push(format!("resize images to {}x{}", width, height)),
+        Some(Size { width, height }) => lines.push(format!(
+            "resize images to {} keeping the aspect ratio",
+            match (width, height) {
+                (Some(width), _) => format!("width {}", width),
+                (_, height) => format!("height {}", height.unwrap_or_default()),
+            },
+        )),
+        None => (),
+    }
+
+    if !args.thermal.is_empty() {
+        lines.push(format!(
+            "convert thermal camera counts to degrees Celsius as {} * counts {} {} for topics: {}",
+            args.thermal_gain,
+            if args.thermal_offset < 0f64 { '-' } else { '+' },
+            args.thermal_offset.abs(),
+            args.thermal.join(", "),
+        ));
+        lines.push(format!(
+            "render thermal images with {} palette{}{}",
+            args.thermal_palette
+                .to_possible_value()
+                .expect("palette values are not skipped")
+                .get_name(),
+            match args.thermal_range {
+                Some(ValueRange::Fixed(min, max)) => format!(" from {} to {} °C", min, max),
+                Some(ValueRange::Percentile(low, high)) => {
+                    format!(" from {}-th to {}-th percentile", low, high)
+                }
+                Some(ValueRange::Auto) | None => " from min to max temperature".to_string(),
+            },
+            if args.thermal_pfm {
+                ", export temperature as float maps"
+            } else {
+                ""
+            },
+        ));
+    }
+
+    match (args.flow_format, args.flow_max) {
+        (_, Some(max)) if max <= 0f32 || max.is_nan() => return Err(AppError::ArgsFlowMax(max)),
+        (FlowFormat::Png, Some(max)) => lines.push(format!(
+            "render optical flow with maximum magnitude of {} pixels",
+            max,
+        )),
+        (FlowFormat::Png, None) => (),
+        (FlowFormat::Flo, _) => lines.push("export optical flow as .flo files".to_string()),
+    }
+
+    if !args.cloud_fields.is_empty() {
+        lines.push(format!(
+            "export point cloud fields as images: {}",
+            args.cloud_fields.join(", "),
+        ));
+    }
+
+    if args.video_format == VideoFormat::AnnexB {
+        lines.push("export video streams as raw H.264/H.265 files".to_string());
+    }
+
+    for pair in &args.detections {
+        lines.push(match args.labels {
+            Some(_) => format!(
+                "export detections from topic {} as labels of images of topic {}",
+                pair.paired, pair.topic,
+            ),
+            None => format!(
+                "draw detections from topic {} onto images of topic {}",
+                pair.paired, pair.topic,
+            ),
+        });
+    }
+    match (args.labels, args.detections.is_empty()) {
+        (Some(_), true) => return Err(AppError::ArgsLabelsWithoutDetections),
+        (Some(LabelFormat::Yolo), false) => {
+            lines.push("write labels in YOLO format, skip frames without detections".to_string())
+        }
+        (Some(LabelFormat::Coco), false) => lines.push(
+            "write all exported images and detections to COCO annotations.json, skip frames without detections"
+                .to_string(),
+        ),
+        (None, _) => (),
+    }
+
+    if !args.segmentation.is_empty() {
+        lines.push(format!(
+            "colorize segmentation masks of topics: {}",
+            args.segmentation.join(", "),
+        ));
+    }
+    if !(0f32..=1f32).contains(&args.seg_alpha) {
+        return Err(AppError::ArgsSegAlpha(args.seg_alpha));
+    }
+    for pair in &args.seg_overlay {
+        lines.push(format!(
+            "blend segmentation masks of topic {} onto images of topic {} with opacity {}",
+            pair.topic, pair.paired, args.seg_alpha,
+        ));
+    }
+    if !args.segmentation.is_empty() || !args.seg_overlay.is_empty() {
+        let classes = palette
+            .classes()
+            .map(|(id, class)| format!("{} {}", id, class.name).trim_end().to_string())
+            .collect::<Vec<String>>();
+        lines.push(match &args.seg_palette {
+            Some(path) if !classes.is_empty() => {
+                format!("segmentation classes from {}: {}", path, classes.join(", "))
+            }
+            _ => "segmentation classes get colors of the PASCAL VOC palette".to_string(),
+        });
+    }
+
     renderer.line(View::RunningExport(lines));
     Ok(())
 }
 
 fn check_for_empty_topics(
-    states: &BTreeMap<u32, TopicState>,
+    states: &TopicStates,
     requested_topics: &[TopicName],
     regex: bool,
     renderer: &Renderer,
 ) {
     let found_topics = states
-        .iter()
-        .filter(|(_, topic)| topic.counter > 0)
-        .map(|(_, v)| v.name.as_str())
-        .collect::<Vec<&str>>();
+        .values()
+        .filter(|state| state.counter > 0)
+        .collect::<Vec<&TopicState>>();
 
     for requested_topic in requested_topics {
-        let contains_topic = found_topics
-            .iter()
-            .any(|found_topic| requested_topic.matches(found_topic));
+        let contains_topic = found_topics.iter().any(|found_topic| {
+            let found_field = found_topic.nested.as_ref().map(|nested| &nested.path);
+            requested_topic.matches(&found_topic.topic)
+                && requested_topic.field.as_ref() == found_field
+        });
 
         if !contains_topic {
-            renderer.line(View::NoMessages(requested_topic.plain.to_string(), regex));
+            renderer.line(View::NoMessages(requested_topic.full.to_string(), regex));
         }
     }
 }
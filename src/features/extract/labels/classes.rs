@@ -0,0 +1,80 @@
+use std::collections::{BTreeSet, HashMap};
+
+/// Numeric class ids up to this value are used as class indices as is
+const MAX_NUMERIC_ID: usize = 1000;
+
+/// Indices of the classes in the exported dataset.
+///
+/// Datasets require class indices from zero. Numeric class ids (e.g. `int64 id`
+/// of ROS1 `vision_msgs`) are kept, so the trained model predicts the same ids,
+/// and the missing ids get placeholder classes. Other ids are sorted by name.
+#[derive(Debug)]
+pub struct ClassMap {
+    /// Class names by index
+    names: Vec<String>,
+    indices: HashMap<String, usize>,
+    /// Class ids are numbers kept as indices
+    numeric: bool,
+}
+
+impl ClassMap {
+    /// # Arguments
+    ///
+    /// * `class_ids` - class ids of all detections, with duplicates
+    pub fn new<'a>(class_ids: impl Iterator<Item = &'a str>) -> Self {
+        let class_ids = class_ids.collect::<BTreeSet<&str>>();
+        let numeric = class_ids
+            .iter()
+            .map(|id| id.parse::<usize>().ok().filter(|id| *id <= MAX_NUMERIC_ID))
+            .collect::<Option<Vec<usize>>>();
+
+        match numeric {
+            Some(numbers) => Self {
+                names: (0..numbers.iter().max().map_or(0, |max| max + 1))
+                    .map(|index| index.to_string())
+                    .collect(),
+                indices: class_ids
+                    .iter()
+                    .zip(numbers)
+                    .map(|(id, index)| (id.to_string(), index))
+                    .collect(),
+                numeric: true,
+            },
+            None => Self {
+                names: class_ids.iter().map(|id| id.to_string()).collect(),
+                indices: class_ids
+                    .iter()
+                    .enumerate()
+                    .map(|(index, id)| (id.to_string(), index))
+                    .collect(),
+                numeric: false,
+            },
+        }
+    }
+
+    pub fn index(&self, class_id: &str) -> Option<usize> {
+        self.indices.get(class_id).copied()
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// COCO category id of the class. Numeric class ids are kept, including 0,
+    /// other classes are numbered from one like in COCO.
+    pub fn category_id(&self, class_id: &str) -> Option<usize> {
+        let index = self.index(class_id)?;
+        Some(if self.numeric { index } else { index + 1 })
+    }
+
+    /// COCO categories `(id, name)` of the classes with detections, sorted by id
+    pub fn categories(&self) -> Vec<(usize, &str)> {
+        let mut categories = self
+            .indices
+            .keys()
+            .filter_map(|class_id| Some((self.category_id(class_id)?, class_id.as_str())))
+            .collect::<Vec<(usize, &str)>>();
+        categories.sort_unstable();
+        categories
+    }
+}
@@ -0,0 +1,140 @@
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::common::de::Time;
+use crate::common::error::AppError;
+use crate::common::json::{number, quote};
+use crate::features::extract::save::SavedImage;
+use crate::vision_msgs::Detection2D;
+
+use super::classes::ClassMap;
+
+/// Images and annotations of the COCO dataset, collected during the export
+/// and written to `annotations.json` at the end
+#[derive(Debug, Default)]
+pub struct CocoDataset {
+    /// JSON objects of the images
+    images: Vec<String>,
+    /// JSON objects of the annotations
+    annotations: Vec<String>,
+}
+
+impl CocoDataset {
+    /// Add the saved image and the annotations made from its detections.
+    /// Boxes are scaled to the size of the saved image and clipped by it,
+    /// detections without class are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `image` - saved image
+    /// * `stamp` - header stamp of the image
+    /// * `size` - width and height of the decoded image, which the detections refer to
+    /// * `detections` - detections of the image, if its topic is paired with detections
+    /// * `classes` - indices of the classes
+    pub fn add_image(
+        &mut self,
+        image: &SavedImage,
+        stamp: Time,
+        size: (u32, u32),
+        detections: Option<&[Detection2D]>,
+        classes: &ClassMap,
+    ) {
+        // resized images are smaller, depth maps and flow files are never resized
+        let (width, height) = (image.width, image.height);
+        let image_id = self.images.len() + 1;
+        let file_name = Path::new(&image.path)
+            .file_name()
+            .map_or_else(|| image.path.as_str().into(), |name| name.to_string_lossy());
+        self.images.push(format!(
+            "{{\"id\": {}, \"file_name\": {}, \"width\": {}, \"height\": {}, \"timestamp\": {}.{:09}}}",
+            image_id,
+            quote(&file_name),
+            width,
+            height,
+            stamp.sec,
+            stamp.nsec,
+        ));
+
+        let scale_x = width as f64 / size.0 as f64;
+        let scale_y = height as f64 / size.1 as f64;
+        let (width, height) = (width as f64, height as f64);
+        for detection in detections.unwrap_or_default() {
+            let (category_id, score) = match detection.hypothesis.as_ref().and_then(|hypothesis| {
+                Some((classes.category_id(&hypothesis.class_id)?, hypothesis.score))
+            }) {
+                Some(category) => category,
+                None => continue,
+            };
+            let left =
+                ((detection.center_x - detection.size_x / 2f64) * scale_x).clamp(0f64, width);
+            let top =
+                ((detection.center_y - detection.size_y / 2f64) * scale_y).clamp(0f64, height);
+            let right =
+                ((detection.center_x + detection.size_x / 2f64) * scale_x).clamp(0f64, width);
+            let bottom =
+                ((detection.center_y + detection.size_y / 2f64) * scale_y).clamp(0f64, height);
+            if right <= left || bottom <= top {
+                continue;
+            }
+            let bbox = [left, top, right - left, bottom - top].map(round);
+            self.annotations.push(format!(
+                "{{\"id\": {}, \"image_id\": {}, \"category_id\": {}, \"bbox\": [{}, {}, {}, {}], \"area\": {}, \"iscrowd\": 0, \"score\": {}}}",
+                self.annotations.len() + 1,
+                image_id,
+                category_id,
+                number(bbox[0]),
+                number(bbox[1]),
+                number(bbox[2]),
+                number(bbox[3]),
+                number(round(bbox[2] * bbox[3])),
+                number(score),
+            ));
+        }
+    }
+
+    /// Write `annotations.json` with the images, annotations and categories
+    pub fn write(&self, output_dir: &str, classes: &ClassMap) -> Result<(), AppError> {
+        let categories = classes
+            .categories()
+            .into_iter()
+            .map(|(id, name)| format!("{{\"id\": {}, \"name\": {}}}", id, quote(name)))
+            .collect::<Vec<String>>();
+
+        let mut json = "{\n".to_string();
+        writeln!(
+            json,
+            "  \"info\": {{\"description\": {}}},",
+            quote(&format!(
+                "Exported by bagimages {}",
+                env!("CARGO_PKG_VERSION")
+            )),
+        )
+        .expect("writing to string doesn't fail");
+        json.push_str("  \"licenses\": [],\n");
+        write_array(&mut json, "images", &self.images, false);
+        write_array(&mut json, "annotations", &self.annotations, false);
+        write_array(&mut json, "categories", &categories, true);
+        json.push_str("}\n");
+
+        let path = format!("{}/annotations.json", output_dir);
+        std::fs::write(&path, json).map_err(|e| AppError::CannotSave(path, e.to_string()))
+    }
+}
+
+/// Write the array of JSON objects with an object per line
+fn write_array(json: &mut String, name: &str, objects: &[String], last: bool) {
+    write!(json, "  \"{}\": [", name).expect("writing to string doesn't fail");
+    for (i, object) in objects.iter().enumerate() {
+        let separator = if i + 1 < objects.len() { "," } else { "" };
+        write!(json, "\n    {}{}", object, separator).expect("writing to string doesn't fail");
+    }
+    if !objects.is_empty() {
+        json.push_str("\n  ");
+    }
+    json.push_str(if last { "]\n" } else { "],\n" });
+}
+
+/// Pixel coordinates with two decimals are precise enough for the boxes
+fn round(value: f64) -> f64 {
+    (value * 100f64).round() / 100f64
+}
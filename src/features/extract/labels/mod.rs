@@ -0,0 +1,89 @@
+use std::cell::RefCell;
+use std::path::Path;
+
+use crate::args::LabelFormat;
+use crate::common::de::Time;
+use crate::common::error::AppError;
+use crate::features::extract::decode::Frame;
+use crate::features::extract::detections::DetectionTopic;
+use crate::features::extract::save::SavedImage;
+use crate::vision_msgs::Detection2D;
+
+use self::classes::ClassMap;
+use self::coco::CocoDataset;
+
+mod classes;
+mod coco;
+mod yolo;
+
+/// Writer of the dataset labels made from the detections paired with the images
+#[derive(Debug)]
+pub struct LabelWriter {
+    format: LabelFormat,
+    /// Classes of all detection topics
+    classes: ClassMap,
+    /// COCO dataset with all saved images, written when the export is finished
+    coco: RefCell<CocoDataset>,
+}
+
+impl LabelWriter {
+    /// # Arguments
+    ///
+    /// * `format` - format of the labels
+    /// * `topics` - all detection topics, to index the classes before the export
+    pub fn new(format: LabelFormat, topics: &[&DetectionTopic]) -> Self {
+        Self {
+            format,
+            classes: ClassMap::new(topics.iter().flat_map(|topic| topic.class_ids())),
+            coco: RefCell::new(CocoDataset::default()),
+        }
+    }
+
+    /// Write the labels of the saved frame, YOLO labels are written to a file per saved image.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - saved frame
+    /// * `images` - saved images of the frame
+    /// * `stamp` - header stamp of the frame
+    /// * `detections` - detections with the same stamp as the frame,
+    ///   if the topic is paired with detections
+    pub fn write(
+        &self,
+        frame: &Frame,
+        images: &[SavedImage],
+        stamp: Time,
+        detections: Option<&[Detection2D]>,
+    ) -> Result<(), AppError> {
+        let size = frame.dimensions()?;
+        for image in images {
+            match (self.format, detections) {
+                (LabelFormat::Yolo, Some(detections)) => {
+                    let label_path = Path::new(&image.path).with_extension("txt");
+                    yolo::write_labels(
+                        &label_path.to_string_lossy(),
+                        detections,
+                        size,
+                        &self.classes,
+                    )?
+                }
+                // YOLO has no list of images, they are found in the directory
+                (LabelFormat::Yolo, None) => (),
+                (LabelFormat::Coco, _) => {
+                    self.coco
+                        .borrow_mut()
+                        .add_image(image, stamp, size, detections, &self.classes)
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the files which describe the whole dataset
+    pub fn finish(&self, output_dir: &str) -> Result<(), AppError> {
+        match self.format {
+            LabelFormat::Yolo => yolo::write_dataset(output_dir, &self.classes),
+            LabelFormat::Coco => self.coco.borrow().write(output_dir, &self.classes),
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use std::fmt::Write;
+
+use crate::common::error::AppError;
+use crate::vision_msgs::Detection2D;
+
+use super::classes::ClassMap;
+
+/// Write the YOLO label file of the image: a line `class cx cy w h` per detection
+/// with the coordinates normalized by the image size.
+/// Boxes are clipped by the image, detections without class are skipped.
+///
+/// # Arguments
+///
+/// * `path` - path to the label file
+/// * `detections` - detections of the image
+/// * `size` - width and height of the image
+/// * `classes` - indices of the classes
+pub fn write_labels(
+    path: &str,
+    detections: &[Detection2D],
+    (width, height): (u32, u32),
+    classes: &ClassMap,
+) -> Result<(), AppError> {
+    let (width, height) = (width as f64, height as f64);
+    let mut text = String::new();
+    for detection in detections {
+        let index = match detection
+            .hypothesis
+            .as_ref()
+            .and_then(|hypothesis| classes.index(&hypothesis.class_id))
+        {
+            Some(index) => index,
+            None => continue,
+        };
+        let left = ((detection.center_x - detection.size_x / 2f64) / width).clamp(0f64, 1f64);
+        let top = ((detection.center_y - detection.size_y / 2f64) / height).clamp(0f64, 1f64);
+        let right = ((detection.center_x + detection.size_x / 2f64) / width).clamp(0f64, 1f64);
+        let bottom = ((detection.center_y + detection.size_y / 2f64) / height).clamp(0f64, 1f64);
+        if right <= left || bottom <= top {
+            continue;
+        }
+        writeln!(
+            text,
+            "{} {:.6} {:.6} {:.6} {:.6}",
+            index,
+            (left + right) / 2f64,
+            (top + bottom) / 2f64,
+            right - left,
+            bottom - top,
+        )
+        .expect("writing to string doesn't fail");
+    }
+    std::fs::write(path, text).map_err(|e| AppError::CannotSave(path.to_string(), e.to_string()))
+}
+
+/// Write `classes.txt` with a class name per line and `data.yaml` for Ultralytics YOLO.
+///
+/// Labels are next to the images, so the output directory is used
+/// both for training and validation, it can be split later.
+pub fn write_dataset(output_dir: &str, classes: &ClassMap) -> Result<(), AppError> {
+    let save = |name: &str, text: String| {
+        let path = format!("{}/{}", output_dir, name);
+        std::fs::write(&path, text).map_err(|e| AppError::CannotSave(path, e.to_string()))
+    };
+
+    let mut names = String::new();
+    for name in classes.names() {
+        writeln!(names, "{}", name).expect("writing to string doesn't fail");
+    }
+    save("classes.txt", names)?;
+
+    // the dataset root is the directory of the file
+    let mut yaml = "train: .\nval: .\n".to_string();
+    writeln!(yaml, "nc: {}", classes.names().len()).expect("writing to string doesn't fail");
+    yaml.push_str("names:\n");
+    for (index, name) in classes.names().iter().enumerate() {
+        writeln!(yaml, "  {}: '{}'", index, name.replace('\'', "''"))
+            .expect("writing to string doesn't fail");
+    }
+    save("data.yaml", yaml)
+}
@@ -1,2 +1,11 @@
+pub mod decode;
+mod detections;
+#[allow(clippy::module_inception)]
 pub mod extract;
+mod labels;
+mod palette;
+mod save;
+mod segmentation;
+mod tone_map;
+mod video;
 pub mod view;
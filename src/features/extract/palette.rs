@@ -0,0 +1,43 @@
+use crate::args::Palette;
+
+/// Color stops of the palettes, positions go from 0 to 1
+const IRONBOW: &[(f32, [u8; 3])] = &[
+    (0.0, [0, 0, 0]),
+    (0.15, [32, 0, 130]),
+    (0.35, [145, 0, 155]),
+    (0.55, [225, 60, 30]),
+    (0.75, [250, 155, 0]),
+    (0.9, [255, 225, 70]),
+    (1.0, [255, 255, 255]),
+];
+const RAINBOW: &[(f32, [u8; 3])] = &[
+    (0.0, [0, 0, 255]),
+    (0.25, [0, 255, 255]),
+    (0.5, [0, 255, 0]),
+    (0.75, [255, 255, 0]),
+    (1.0, [255, 0, 0]),
+];
+const GRAYSCALE: &[(f32, [u8; 3])] = &[(0.0, [0, 0, 0]), (1.0, [255, 255, 255])];
+
+/// Color of the palette at the position `t` from `[0, 1]`
+pub fn colorize(palette: Palette, t: f32) -> [u8; 3] {
+    let stops = match palette {
+        Palette::Ironbow => IRONBOW,
+        Palette::Rainbow => RAINBOW,
+        Palette::Grayscale => GRAYSCALE,
+    };
+    let upper = stops
+        .iter()
+        .position(|(position, _)| *position >= t)
+        .unwrap_or(stops.len() - 1)
+        .max(1);
+    let (from, from_color) = stops[upper - 1];
+    let (to, to_color) = stops[upper];
+    let k = ((t - from) / (to - from)).clamp(0f32, 1f32);
+    let mut color = [0u8; 3];
+    for (i, c) in color.iter_mut().enumerate() {
+        let (a, b) = (from_color[i] as f32, to_color[i] as f32);
+        *c = (a + (b - a) * k).round() as u8;
+    }
+    color
+}
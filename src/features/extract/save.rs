@@ -0,0 +1,173 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView, ImageBuffer, Luma, Rgb};
+
+use crate::args::{Args, DepthFormat, DepthUnits, FlowFormat, OutputFormat};
+use crate::common::error::AppError;
+use crate::common::flo::write_flo;
+use crate::common::pfm::write_pfm;
+use crate::common::range::ValueRange;
+use crate::features::extract::decode::{DepthMap, EncodedImage, FlowField, Frame, ThermalMap};
+use crate::features::extract::tone_map::ToneMapping;
+
+/// Image written to disk
+#[derive(Debug)]
+pub struct SavedImage {
+    pub path: String,
+    /// Size of the written image, which differs from the frame if it's resized
+    pub width: u32,
+    pub height: u32,
+}
+
+impl SavedImage {
+    fn new(path: String, (width, height): (u32, u32)) -> Self {
+        Self {
+            path,
+            width,
+            height,
+        }
+    }
+}
+
+/// Save decoded frame to disk.
+///
+/// # Arguments
+///
+/// * `frame` - decoded frame
+/// * `base_path` - path to the output file without extension
+/// * `args` - app arguments with output options
+///
+/// Returns the saved images, without additional files like PFM temperature.
+pub fn save(frame: &Frame, base_path: &str, args: &Args) -> Result<Vec<SavedImage>, AppError> {
+    match frame {
+        Frame::Image(image) => save_image(image, base_path, args).map(|path| vec![path]),
+        Frame::Depth(depth) => save_depth(depth, base_path, args).map(|path| vec![path]),
+        Frame::Flow(flow) => save_flow(flow, base_path, args).map(|path| vec![path]),
+        Frame::Thermal(thermal) => save_thermal(thermal, base_path, args).map(|path| vec![path]),
+        Frame::Split(images) => images
+            .iter()
+            .map(|(suffix, image)| save_image(image, &format!("{}_{}", base_path, suffix), args))
+            .collect(),
+        Frame::Encoded(encoded) => save_encoded(encoded, base_path).map(|path| vec![path]),
+    }
+}
+
+/// Save viewable image with the output format and size from the app arguments
+fn save_image(image: &DynamicImage, base_path: &str, args: &Args) -> Result<SavedImage, AppError> {
+    let resized = args.resize.map(|size| match (size.width, size.height) {
+        (Some(width), Some(height)) => image.resize_exact(width, height, FilterType::Lanczos3),
+        (width, height) => image.resize(
+            width.unwrap_or(u32::MAX),
+            height.unwrap_or(u32::MAX),
+            FilterType::Lanczos3,
+        ),
+    });
+    let image = resized.as_ref().unwrap_or(image);
+
+    match args.format {
+        Some(OutputFormat::Png) | None => save_png(image, base_path),
+        Some(OutputFormat::Jpeg) => save_jpeg(image, base_path, args.jpeg_quality),
+    }
+}
+
+fn save_png(image: &DynamicImage, base_path: &str) -> Result<SavedImage, AppError> {
+    let save_path = format!("{}.png", base_path);
+    match image.save_with_format(&save_path, image::ImageFormat::Png) {
+        Ok(()) => Ok(SavedImage::new(save_path, image.dimensions())),
+        Err(e) => Err(AppError::CannotSave(save_path, e.to_string())),
+    }
+}
+
+/// JPEG supports only 8-bit grayscale and RGB images
+fn save_jpeg(image: &DynamicImage, base_path: &str, quality: u8) -> Result<SavedImage, AppError> {
+    let save_path = format!("{}.jpg", base_path);
+    let image = match image {
+        DynamicImage::ImageLuma8(_) | DynamicImage::ImageRgb8(_) => image.clone(),
+        DynamicImage::ImageLuma16(_)
+        | DynamicImage::ImageLumaA8(_)
+        | DynamicImage::ImageLumaA16(_) => DynamicImage::ImageLuma8(image.to_luma8()),
+        _ => DynamicImage::ImageRgb8(image.to_rgb8()),
+    };
+    let file = File::create(&save_path)
+        .map_err(|e| AppError::CannotSave(save_path.clone(), e.to_string()))?;
+    let mut writer = BufWriter::new(file);
+    // errors of the last write are reported only by the flush
+    let saved = JpegEncoder::new_with_quality(&mut writer, quality)
+        .encode_image(&image)
+        .map_err(|e| e.to_string())
+        .and_then(|()| writer.flush().map_err(|e| e.to_string()));
+    match saved {
+        Ok(()) => Ok(SavedImage::new(save_path, image.dimensions())),
+        Err(e) => Err(AppError::CannotSave(save_path, e)),
+    }
+}
+
+fn save_encoded(encoded: &EncodedImage, base_path: &str) -> Result<SavedImage, AppError> {
+    let save_path = format!("{}.{}", base_path, encoded.extension);
+    match std::fs::write(&save_path, &encoded.data) {
+        Ok(()) => Ok(SavedImage::new(save_path, encoded.dimensions()?)),
+        Err(e) => Err(AppError::CannotSave(save_path, e.to_string())),
+    }
+}
+
+fn save_depth(depth: &DepthMap, base_path: &str, args: &Args) -> Result<SavedImage, AppError> {
+    match args.depth_format {
+        DepthFormat::Png16 => {
+            let buffer: ImageBuffer<Luma<u16>, Vec<u16>> =
+                ImageBuffer::from_vec(depth.width, depth.height, depth.to_millimetres())
+                    .expect("depth map size is checked when decoding");
+            save_png(&DynamicImage::ImageLuma16(buffer), base_path)
+        }
+        DepthFormat::Pfm => {
+            let save_path = format!("{}.pfm", base_path);
+            let data = match args.depth_units {
+                Some(DepthUnits::Mm) => depth.to_units(0.001),
+                Some(DepthUnits::M) | None => depth.to_units(1.0),
+            };
+            match write_pfm(&save_path, depth.width, depth.height, 1, &data) {
+                Ok(()) => Ok(SavedImage::new(save_path, (depth.width, depth.height))),
+                Err(e) => Err(AppError::CannotSave(save_path, e.to_string())),
+            }
+        }
+    }
+}
+
+fn save_flow(flow: &FlowField, base_path: &str, args: &Args) -> Result<SavedImage, AppError> {
+    match args.flow_format {
+        FlowFormat::Png => save_image(&flow.to_image(args.flow_max), base_path, args),
+        FlowFormat::Flo => {
+            let save_path = format!("{}.flo", base_path);
+            match write_flo(&save_path, flow.width, flow.height, &flow.data) {
+                Ok(()) => Ok(SavedImage::new(save_path, (flow.width, flow.height))),
+                Err(e) => Err(AppError::CannotSave(save_path, e.to_string())),
+            }
+        }
+    }
+}
+
+fn save_thermal(
+    thermal: &ThermalMap,
+    base_path: &str,
+    args: &Args,
+) -> Result<SavedImage, AppError> {
+    let tone_mapping = ToneMapping {
+        range: args.thermal_range.unwrap_or(ValueRange::Auto),
+        fill: Rgb([0, 0, 0]),
+        log_scale: false,
+    };
+    let image = save_image(
+        &thermal.to_image(args.thermal_palette, &tone_mapping),
+        base_path,
+        args,
+    )?;
+
+    if args.thermal_pfm {
+        let save_path = format!("{}.pfm", base_path);
+        write_pfm(&save_path, thermal.width, thermal.height, 1, &thermal.data)
+            .map_err(|e| AppError::CannotSave(save_path, e.to_string()))?;
+    }
+    Ok(image)
+}
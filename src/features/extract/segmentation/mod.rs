@@ -0,0 +1,85 @@
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, ImageBuffer, Luma, Rgb, RgbImage};
+
+use crate::common::error::AppError;
+use crate::features::extract::decode::Frame;
+
+mod overlay;
+mod palette;
+
+pub use self::overlay::OverlayImages;
+pub use self::palette::LabelPalette;
+
+/// Class id of every pixel of the segmentation mask
+pub type ClassIds = ImageBuffer<Luma<u16>, Vec<u16>>;
+
+/// Class ids of the decoded mask, which is a grayscale image
+/// from the segmentation topic or a compressed PNG
+///
+/// # Arguments
+///
+/// * `frame` - decoded mask
+/// * `topic` - topic of the mask for the error message
+pub fn class_ids(frame: &Frame, topic: &str) -> Result<ClassIds, AppError> {
+    match frame {
+        Frame::Image(DynamicImage::ImageLuma8(image)) => Ok(ImageBuffer::from_fn(
+            image.width(),
+            image.height(),
+            |x, y| Luma([image.get_pixel(x, y).0[0] as u16]),
+        )),
+        Frame::Image(DynamicImage::ImageLuma16(image)) => Ok(image.clone()),
+        _ => Err(AppError::NotLabelMap(topic.to_string())),
+    }
+}
+
+/// Render the mask with the colors of the classes, classes which aren't
+/// blended onto the images are black
+pub fn colorize(ids: &ClassIds, palette: &LabelPalette) -> DynamicImage {
+    let colors = class_colors(ids, palette);
+    let image = RgbImage::from_fn(ids.width(), ids.height(), |x, y| {
+        Rgb(colors[ids.get_pixel(x, y).0[0] as usize].unwrap_or_default())
+    });
+    DynamicImage::ImageRgb8(image)
+}
+
+/// Blend the colors of the classes onto the image.
+/// The mask is scaled to the size of the image if they differ.
+///
+/// # Arguments
+///
+/// * `image` - image which the mask is computed from
+/// * `ids` - class ids of the mask
+/// * `palette` - colors of the classes
+/// * `alpha` - opacity of the colors, from 0 to 1
+pub fn blend(
+    image: &DynamicImage,
+    ids: &ClassIds,
+    palette: &LabelPalette,
+    alpha: f32,
+) -> DynamicImage {
+    let mut output = image.to_rgb8();
+    let scaled;
+    let ids = if ids.dimensions() == output.dimensions() {
+        ids
+    } else {
+        // class ids can't be interpolated
+        scaled = imageops::resize(ids, output.width(), output.height(), FilterType::Nearest);
+        &scaled
+    };
+
+    let colors = class_colors(ids, palette);
+    for (pixel, id) in output.pixels_mut().zip(ids.pixels()) {
+        if let Some(color) = colors[id.0[0] as usize] {
+            for (c, class) in pixel.0.iter_mut().zip(color) {
+                *c = (*c as f32 * (1f32 - alpha) + class as f32 * alpha).round() as u8;
+            }
+        }
+    }
+    DynamicImage::ImageRgb8(output)
+}
+
+/// Colors of the class ids up to the maximum id of the mask
+fn class_colors(ids: &ClassIds, palette: &LabelPalette) -> Vec<Option<[u8; 3]>> {
+    let max = ids.pixels().map(|id| id.0[0]).max().unwrap_or_default();
+    (0..=max).map(|id| palette.color(id)).collect()
+}
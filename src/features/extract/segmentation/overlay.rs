@@ -0,0 +1,105 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+
+use image::DynamicImage;
+
+use crate::args::Args;
+use crate::common::de::from_bytes;
+use crate::common::error::AppError;
+use crate::features::extract::decode::{decode_compressed, DecodeOptions, DecoderRegistry, Frame};
+use crate::sensor_msgs;
+
+/// Maximum number of image messages kept until their masks arrive
+const MAX_QUEUED: usize = 64;
+
+/// Recent messages of the image topic which the segmentation masks are blended onto.
+///
+/// Masks are computed from the images, so they are recorded later and are matched
+/// with the queued images by header stamp.
+#[derive(Debug)]
+pub struct OverlayImages {
+    /// Topic of the images
+    pub topic: String,
+    /// Are color channels of the images mixed up?
+    invert_channels: bool,
+    /// Connections of the topic, `true` for `sensor_msgs/CompressedImage`
+    connections: RefCell<HashMap<u32, bool>>,
+    /// Stamps and raw messages, images are decoded only if they get masks
+    queue: RefCell<VecDeque<(u64, bool, Vec<u8>)>>,
+}
+
+impl OverlayImages {
+    pub fn new(topic: String, invert_channels: bool) -> Self {
+        Self {
+            topic,
+            invert_channels,
+            connections: RefCell::new(HashMap::new()),
+            queue: RefCell::new(VecDeque::new()),
+        }
+    }
+
+    /// Receive messages of the connection
+    ///
+    /// # Arguments
+    ///
+    /// * `conn_id` - connection id of the image topic
+    /// * `compressed` - messages are `sensor_msgs/CompressedImage`
+    pub fn add_connection(&self, conn_id: u32, compressed: bool) {
+        self.connections.borrow_mut().insert(conn_id, compressed);
+    }
+
+    /// Queue the message if it's an image of the topic
+    pub fn push(&self, conn_id: u32, data: &[u8]) -> Result<(), AppError> {
+        let compressed = match self.connections.borrow().get(&conn_id) {
+            Some(compressed) => *compressed,
+            None => return Ok(()),
+        };
+        let stamp = if compressed {
+            from_bytes::<sensor_msgs::CompressedImage>(data)?
+                .header
+                .stamp
+        } else {
+            from_bytes::<sensor_msgs::Image>(data)?.header.stamp
+        };
+        let mut queue = self.queue.borrow_mut();
+        queue.push_back((stamp.nanos(), compressed, data.to_vec()));
+        if queue.len() > MAX_QUEUED {
+            queue.pop_front();
+        }
+        Ok(())
+    }
+
+    /// Decode the image with the stamp of the mask, older images are dropped.
+    /// Returns `None` if the topic has no image with the stamp.
+    pub fn take(
+        &self,
+        stamp: u64,
+        decoders: &DecoderRegistry,
+        args: &Args,
+    ) -> Result<Option<DynamicImage>, AppError> {
+        let mut queue = self.queue.borrow_mut();
+        while queue.front().is_some_and(|(queued, _, _)| *queued < stamp) {
+            queue.pop_front();
+        }
+        // other masks with the same stamp may use the image too, so it's kept
+        let (compressed, data) = match queue.front() {
+            Some((queued, compressed, data)) if *queued == stamp => (*compressed, data),
+            _ => return Ok(None),
+        };
+
+        let options = DecodeOptions::new(args, self.invert_channels, false, false);
+        let frame = if compressed {
+            decode_compressed(
+                &from_bytes::<sensor_msgs::CompressedImage>(data)?,
+                &options,
+                true,
+            )?
+        } else {
+            decoders.decode(&from_bytes::<sensor_msgs::Image>(data)?, &options)?
+        };
+        match frame {
+            Frame::Image(image) => Ok(Some(image)),
+            _ => Err(AppError::OverlayNotImage(self.topic.clone())),
+        }
+    }
+}
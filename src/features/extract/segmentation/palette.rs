@@ -0,0 +1,82 @@
+use std::collections::BTreeMap;
+
+use crate::common::color::parse_color;
+use crate::common::error::AppError;
+
+/// Class of the segmentation masks from the palette file
+#[derive(Debug)]
+pub struct LabelClass {
+    /// Color of the class, `None` if the class isn't blended onto the images
+    pub color: Option<[u8; 3]>,
+    pub name: String,
+}
+
+/// Colors and names of the classes in the segmentation masks by class id.
+///
+/// Classes missing from the palette get the colors of the PASCAL VOC palette,
+/// and class `0` is the background which isn't blended onto the images.
+#[derive(Debug, Default)]
+pub struct LabelPalette {
+    classes: BTreeMap<u16, LabelClass>,
+}
+
+impl LabelPalette {
+    /// Read the palette file with a line `ID COLOR NAME` per class, e.g. `7 #804080 road`.
+    /// The color is `#RRGGBB`, `R,G,B` or `none` for classes which aren't blended
+    /// onto the images. Empty lines and lines starting with `#` are skipped.
+    pub fn load(path: &str) -> Result<Self, AppError> {
+        let invalid = |cause: String| AppError::InvalidPalette(path.to_string(), cause);
+        let text = std::fs::read_to_string(path).map_err(|e| invalid(e.to_string()))?;
+
+        let mut classes = BTreeMap::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid_line = |cause: String| invalid(format!("line {}: {}", number + 1, cause));
+            // the fields are separated by any whitespace, the name can contain spaces
+            let mut words = line.split_whitespace();
+            let id = words.next().unwrap_or_default();
+            let id = id
+                .parse::<u16>()
+                .map_err(|_| invalid_line(format!("`{}` is not a class id", id)))?;
+            let color = match words.next() {
+                Some("none") => None,
+                Some(color) => Some(parse_color(color).map_err(invalid_line)?.0),
+                None => return Err(invalid_line("the color is missing".to_string())),
+            };
+            let name = words.collect::<Vec<&str>>().join(" ");
+            classes.insert(id, LabelClass { color, name });
+        }
+        Ok(Self { classes })
+    }
+
+    /// Color of the class, `None` if it isn't blended onto the images
+    pub fn color(&self, id: u16) -> Option<[u8; 3]> {
+        match self.classes.get(&id) {
+            Some(class) => class.color,
+            None if id == 0 => None,
+            None => Some(voc_color(id)),
+        }
+    }
+
+    /// Classes from the palette file sorted by id
+    pub fn classes(&self) -> impl Iterator<Item = (&u16, &LabelClass)> {
+        self.classes.iter()
+    }
+}
+
+/// Color of the class in the PASCAL VOC palette, which spreads the bits
+/// of the id over the channels so neighbouring ids get distinct colors
+fn voc_color(id: u16) -> [u8; 3] {
+    let mut color = [0u8; 3];
+    let mut id = id;
+    for bit in (0..8).rev() {
+        for (channel, c) in color.iter_mut().enumerate() {
+            *c |= (((id >> channel) & 1) as u8) << bit;
+        }
+        id >>= 3;
+    }
+    color
+}
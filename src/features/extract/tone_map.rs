@@ -0,0 +1,138 @@
+use image::{DynamicImage, GrayImage, ImageBuffer, Rgb, RgbImage, RgbaImage};
+
+use crate::common::range::ValueRange;
+
+/// Conversion of arbitrary numeric values into viewable 8-bit images
+#[derive(Debug, Clone)]
+pub struct ToneMapping {
+    /// Range of values mapped to `0..255`
+    pub range: ValueRange,
+    /// Color of `NaN` and infinite values
+    pub fill: Rgb<u8>,
+    /// Use logarithmic scale instead of linear
+    pub log_scale: bool,
+}
+
+impl ToneMapping {
+    /// Actual `(min, max)` of the range for the values
+    pub fn resolve_range(&self, values: &[f32]) -> (f64, f64) {
+        match self.range {
+            ValueRange::Fixed(min, max) => (min, max),
+            ValueRange::Auto => values
+                .iter()
+                .filter(|v| v.is_finite())
+                .fold(None, |acc: Option<(f64, f64)>, v| {
+                    let v = *v as f64;
+                    Some(match acc {
+                        Some((min, max)) => (min.min(v), max.max(v)),
+                        None => (v, v),
+                    })
+                })
+                .unwrap_or((0f64, 1f64)),
+            ValueRange::Percentile(low, high) => {
+                let mut finite = values
+                    .iter()
+                    .filter(|v| v.is_finite())
+                    .map(|v| *v as f64)
+                    .collect::<Vec<f64>>();
+                if finite.is_empty() {
+                    return (0f64, 1f64);
+                }
+                finite.sort_unstable_by(|a, b| a.total_cmp(b));
+                let at = |percentile: f64| {
+                    let index = (percentile / 100f64 * (finite.len() - 1) as f64).round();
+                    finite[index as usize]
+                };
+                (at(low), at(high))
+            }
+        }
+    }
+
+    /// Map values into `[0, 1]`, `None` for `NaN` and infinite values.
+    ///
+    /// The range is resolved once for all values.
+    pub fn normalize(&self, values: &[f32]) -> Vec<Option<f32>> {
+        let (min, max) = self.resolve_range(values);
+        values
+            .iter()
+            .map(|v| {
+                if v.is_finite() {
+                    Some(self.normalize_value(*v as f64, min, max))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Map the value into `[0, 1]` with the resolved range.
+    ///
+    /// Log scale uses `ln(v / min)` for positive ranges
+    /// and `ln(1 + v - min)` for ranges which include zero or negative values.
+    pub fn normalize_value(&self, value: f64, min: f64, max: f64) -> f32 {
+        let value = value.clamp(min, max);
+        let t = match self.log_scale {
+            false => (value - min) / (max - min),
+            true if min > 0f64 => (value / min).ln() / (max / min).ln(),
+            true => (1f64 + value - min).ln() / (1f64 + max - min).ln(),
+        };
+        if t.is_finite() {
+            t as f32
+        } else {
+            0f32
+        }
+    }
+
+    /// Convert values into 8-bit image.
+    ///
+    /// All channels share the same range. Single channel values become
+    /// a grayscale image (or RGB image if the fill color is not gray),
+    /// 3 and 4 channel values become RGB and RGBA images.
+    /// Returns `None` for other number of channels or if the values don't match the size.
+    pub fn to_image(
+        &self,
+        width: u32,
+        height: u32,
+        channels: usize,
+        values: &[f32],
+    ) -> Option<DynamicImage> {
+        if values.len() != width as usize * height as usize * channels {
+            return None;
+        }
+        let normalized = self.normalize(values);
+        let to_u8 = |t: f32| (t * 255f32).round() as u8;
+        let [fr, fg, fb] = self.fill.0;
+
+        match channels {
+            1 if fr == fg && fg == fb => {
+                let pixels = normalized.iter().map(|t| t.map(to_u8).unwrap_or(fr));
+                GrayImage::from_vec(width, height, pixels.collect()).map(DynamicImage::ImageLuma8)
+            }
+            1 => {
+                let pixels = normalized.iter().flat_map(|t| match t {
+                    Some(t) => [to_u8(*t); 3],
+                    None => self.fill.0,
+                });
+                RgbImage::from_vec(width, height, pixels.collect()).map(DynamicImage::ImageRgb8)
+            }
+            3 => {
+                let pixels = normalized.chunks_exact(3).flat_map(|p| match p {
+                    [Some(r), Some(g), Some(b)] => [to_u8(*r), to_u8(*g), to_u8(*b)],
+                    _ => self.fill.0,
+                });
+                RgbImage::from_vec(width, height, pixels.collect()).map(DynamicImage::ImageRgb8)
+            }
+            4 => {
+                let pixels = normalized.chunks_exact(4).flat_map(|p| match p {
+                    [Some(r), Some(g), Some(b), Some(a)] => {
+                        [to_u8(*r), to_u8(*g), to_u8(*b), to_u8(*a)]
+                    }
+                    _ => [fr, fg, fb, u8::MAX],
+                });
+                ImageBuffer::from_vec(width, height, pixels.collect())
+                    .map(|buffer: RgbaImage| DynamicImage::ImageRgba8(buffer))
+            }
+            _ => None,
+        }
+    }
+}
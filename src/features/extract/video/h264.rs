@@ -0,0 +1,225 @@
+use crate::common::error::AppError;
+use crate::common::mp4::{mp4_box, SampleEntry};
+
+use super::nal::BitReader;
+
+const NAL_IDR: u8 = 5;
+const NAL_SPS: u8 = 7;
+const NAL_PPS: u8 = 8;
+const NAL_AUD: u8 = 9;
+
+/// Profiles with the chroma format and bit depth in the SPS
+const HIGH_PROFILES: [u8; 12] = [100, 110, 122, 244, 44, 83, 86, 118, 128, 138, 139, 134];
+
+/// Parameter sets required to decode the stream
+pub const PARAMETER_SETS: &[u8] = &[NAL_SPS, NAL_PPS];
+
+pub fn nal_type(nal: &[u8]) -> u8 {
+    nal[0] & 0x1f
+}
+
+pub fn is_keyframe(nal_type: u8) -> bool {
+    nal_type == NAL_IDR
+}
+
+pub fn is_parameter_set(nal_type: u8) -> bool {
+    nal_type == NAL_SPS || nal_type == NAL_PPS
+}
+
+/// Access unit delimiters are not stored in MP4 samples
+pub fn is_delimiter(nal_type: u8) -> bool {
+    nal_type == NAL_AUD
+}
+
+/// Fields of the sequence parameter set required for the MP4 sample entry
+///
+/// Specification: ITU-T H.264, 7.3.2.1.1
+struct Sps {
+    profile_idc: u8,
+    constraint_flags: u8,
+    level_idc: u8,
+    chroma_format_idc: u32,
+    bit_depth_luma_minus8: u32,
+    bit_depth_chroma_minus8: u32,
+    width: u32,
+    height: u32,
+}
+
+fn parse_sps(nal: &[u8]) -> Result<Sps, AppError> {
+    let mut r = BitReader::new(&nal[1..]);
+    let profile_idc = r.read_bits(8)? as u8;
+    let constraint_flags = r.read_bits(8)? as u8;
+    let level_idc = r.read_bits(8)? as u8;
+    r.read_ue()?; // seq_parameter_set_id
+
+    let mut chroma_format_idc = 1;
+    let mut separate_colour_plane = false;
+    let mut bit_depth_luma_minus8 = 0;
+    let mut bit_depth_chroma_minus8 = 0;
+    if HIGH_PROFILES.contains(&profile_idc) {
+        chroma_format_idc = r.read_ue()?;
+        if chroma_format_idc == 3 {
+            separate_colour_plane = r.read_bit()? == 1;
+        }
+        bit_depth_luma_minus8 = r.read_ue()?;
+        bit_depth_chroma_minus8 = r.read_ue()?;
+        r.read_bit()?; // qpprime_y_zero_transform_bypass_flag
+        if r.read_bit()? == 1 {
+            let lists = if chroma_format_idc == 3 { 12 } else { 8 };
+            for i in 0..lists {
+                if r.read_bit()? == 1 {
+                    skip_scaling_list(&mut r, if i < 6 { 16 } else { 64 })?;
+                }
+            }
+        }
+    }
+
+    r.read_ue()?; // log2_max_frame_num_minus4
+    match r.read_ue()? {
+        0 => {
+            r.read_ue()?; // log2_max_pic_order_cnt_lsb_minus4
+        }
+        1 => {
+            r.read_bit()?; // delta_pic_order_always_zero_flag
+            r.read_se()?; // offset_for_non_ref_pic
+            r.read_se()?; // offset_for_top_to_bottom_field
+            for _ in 0..r.read_ue()? {
+                r.read_se()?; // offset_for_ref_frame
+            }
+        }
+        _ => (),
+    }
+    r.read_ue()?; // max_num_ref_frames
+    r.read_bit()?; // gaps_in_frame_num_value_allowed_flag
+
+    let width_in_mbs = r.read_ue()? + 1;
+    let height_in_map_units = r.read_ue()? + 1;
+    let frame_mbs_only = r.read_bit()?;
+    if frame_mbs_only == 0 {
+        r.read_bit()?; // mb_adaptive_frame_field_flag
+    }
+    r.read_bit()?; // direct_8x8_inference_flag
+
+    let mut width = width_in_mbs * 16;
+    let mut height = (2 - frame_mbs_only) * height_in_map_units * 16;
+    if r.read_bit()? == 1 {
+        let (sub_width, sub_height) = match (chroma_format_idc, separate_colour_plane) {
+            (0, _) | (3, true) => (1, 1),
+            (1, _) => (2, 2),
+            (2, _) => (2, 1),
+            _ => (1, 1),
+        };
+        let crop_x = sub_width;
+        let crop_y = sub_height * (2 - frame_mbs_only);
+        let (left, right) = (r.read_ue()?, r.read_ue()?);
+        let (top, bottom) = (r.read_ue()?, r.read_ue()?);
+        width = width.saturating_sub(crop_x * (left + right));
+        height = height.saturating_sub(crop_y * (top + bottom));
+    }
+
+    Ok(Sps {
+        profile_idc,
+        constraint_flags,
+        level_idc,
+        chroma_format_idc,
+        bit_depth_luma_minus8,
+        bit_depth_chroma_minus8,
+        width,
+        height,
+    })
+}
+
+fn skip_scaling_list(r: &mut BitReader, size: usize) -> Result<(), AppError> {
+    let mut last = 8;
+    let mut next = 8;
+    for _ in 0..size {
+        if next != 0 {
+            next = (last + r.read_se()? + 256) % 256;
+        }
+        if next != 0 {
+            last = next;
+        }
+    }
+    Ok(())
+}
+
+/// `avc1` sample entry with the `avcC` decoder configuration record
+///
+/// Specification: ISO/IEC 14496-15, 5.3.3.1
+///
+/// # Arguments
+///
+/// * `parameter_sets` - SPS and PPS NAL units of the stream
+pub fn sample_entry(parameter_sets: &[&[u8]]) -> Result<SampleEntry, AppError> {
+    let with_type = |tp: u8| {
+        parameter_sets
+            .iter()
+            .filter(|nal| nal_type(nal) == tp)
+            .copied()
+            .collect::<Vec<&[u8]>>()
+    };
+    let (sps_list, pps_list) = (with_type(NAL_SPS), with_type(NAL_PPS));
+    let sps = parse_sps(sps_list.first().ok_or(AppError::OutOfBounds)?)?;
+
+    let mut avcc = vec![
+        1, // configuration version
+        sps.profile_idc,
+        sps.constraint_flags,
+        sps.level_idc,
+        0xfc | 3, // 4 bytes of NAL unit length
+        0xe0 | sps_list.len() as u8,
+    ];
+    for nal in &sps_list {
+        avcc.extend_from_slice(&(nal.len() as u16).to_be_bytes());
+        avcc.extend_from_slice(nal);
+    }
+    avcc.push(pps_list.len() as u8);
+    for nal in &pps_list {
+        avcc.extend_from_slice(&(nal.len() as u16).to_be_bytes());
+        avcc.extend_from_slice(nal);
+    }
+    if HIGH_PROFILES.contains(&sps.profile_idc) {
+        avcc.push(0xfc | sps.chroma_format_idc as u8);
+        avcc.push(0xf8 | sps.bit_depth_luma_minus8 as u8);
+        avcc.push(0xf8 | sps.bit_depth_chroma_minus8 as u8);
+        avcc.push(0); // SPS extensions
+    }
+
+    Ok(SampleEntry {
+        kind: *b"avc1",
+        width: sps.width,
+        height: sps.height,
+        config: mp4_box(b"avcC", &avcc),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// SPS of a 1280x720 stream from x264, High profile, level 3.1
+    const SPS: &[u8] = &[
+        0x67, 0x64, 0x00, 0x1f, 0xac, 0xd9, 0x40, 0x50, 0x05, 0xbb, 0x01, 0x10, 0x00, 0x00, 0x03,
+        0x00, 0x10, 0x00, 0x00, 0x03, 0x03, 0xc0, 0xf1, 0x83, 0x19, 0x60,
+    ];
+
+    #[test]
+    fn parse_sps_of_high_profile() {
+        let sps = parse_sps(SPS).unwrap();
+        assert_eq!(nal_type(SPS), NAL_SPS);
+        assert_eq!((sps.width, sps.height), (1280, 720));
+        assert_eq!(sps.profile_idc, 100);
+        assert_eq!(sps.constraint_flags, 0);
+        assert_eq!(sps.level_idc, 31);
+        assert_eq!(sps.chroma_format_idc, 1);
+        assert_eq!(
+            (sps.bit_depth_luma_minus8, sps.bit_depth_chroma_minus8),
+            (0, 0)
+        );
+    }
+
+    #[test]
+    fn parse_truncated_sps() {
+        assert!(parse_sps(&SPS[..8]).is_err());
+    }
+}
@@ -0,0 +1,214 @@
+use crate::common::error::AppError;
+use crate::common::mp4::{mp4_box, SampleEntry};
+
+use super::nal::BitReader;
+
+const NAL_BLA_W_LP: u8 = 16;
+const NAL_CRA: u8 = 21;
+const NAL_VPS: u8 = 32;
+const NAL_SPS: u8 = 33;
+const NAL_PPS: u8 = 34;
+const NAL_AUD: u8 = 35;
+
+/// Parameter sets required to decode the stream
+pub const PARAMETER_SETS: &[u8] = &[NAL_VPS, NAL_SPS, NAL_PPS];
+
+pub fn nal_type(nal: &[u8]) -> u8 {
+    (nal[0] >> 1) & 0x3f
+}
+
+/// Intra random access point pictures: BLA, IDR and CRA
+pub fn is_keyframe(nal_type: u8) -> bool {
+    (NAL_BLA_W_LP..=NAL_CRA).contains(&nal_type)
+}
+
+pub fn is_parameter_set(nal_type: u8) -> bool {
+    (NAL_VPS..=NAL_PPS).contains(&nal_type)
+}
+
+/// Access unit delimiters are not stored in MP4 samples
+pub fn is_delimiter(nal_type: u8) -> bool {
+    nal_type == NAL_AUD
+}
+
+/// Fields of the sequence parameter set required for the MP4 sample entry
+///
+/// Specification: ITU-T H.265, 7.3.2.2.1
+struct Sps {
+    /// `general_profile_space`, `general_tier_flag` and `general_profile_idc`
+    profile: u8,
+    profile_compatibility_flags: u32,
+    constraint_flags: [u8; 6],
+    level_idc: u8,
+    max_sub_layers: u8,
+    temporal_id_nesting: u8,
+    chroma_format_idc: u32,
+    bit_depth_luma_minus8: u32,
+    bit_depth_chroma_minus8: u32,
+    width: u32,
+    height: u32,
+}
+
+fn parse_sps(nal: &[u8]) -> Result<Sps, AppError> {
+    let mut r = BitReader::new(nal.get(2..).ok_or(AppError::OutOfBounds)?);
+    r.read_bits(4)?; // sps_video_parameter_set_id
+    let max_sub_layers_minus1 = r.read_bits(3)?;
+    let temporal_id_nesting = r.read_bit()? as u8;
+
+    // profile_tier_level
+    let profile = r.read_bits(8)? as u8;
+    let profile_compatibility_flags = r.read_bits(32)?;
+    let mut constraint_flags = [0u8; 6];
+    for flags in constraint_flags.iter_mut() {
+        *flags = r.read_bits(8)? as u8;
+    }
+    let level_idc = r.read_bits(8)? as u8;
+    let mut sub_layers = Vec::new();
+    for _ in 0..max_sub_layers_minus1 {
+        let profile_present = r.read_bit()? == 1;
+        let level_present = r.read_bit()? == 1;
+        sub_layers.push((profile_present, level_present));
+    }
+    if max_sub_layers_minus1 > 0 {
+        r.skip_bits(2 * (8 - max_sub_layers_minus1 as usize))?;
+    }
+    for (profile_present, level_present) in sub_layers {
+        if profile_present {
+            r.skip_bits(88)?;
+        }
+        if level_present {
+            r.skip_bits(8)?;
+        }
+    }
+
+    r.read_ue()?; // sps_seq_parameter_set_id
+    let chroma_format_idc = r.read_ue()?;
+    if chroma_format_idc == 3 {
+        r.read_bit()?; // separate_colour_plane_flag
+    }
+    let mut width = r.read_ue()?;
+    let mut height = r.read_ue()?;
+    if r.read_bit()? == 1 {
+        let (sub_width, sub_height) = match chroma_format_idc {
+            1 => (2, 2),
+            2 => (2, 1),
+            _ => (1, 1),
+        };
+        let (left, right) = (r.read_ue()?, r.read_ue()?);
+        let (top, bottom) = (r.read_ue()?, r.read_ue()?);
+        width = width.saturating_sub(sub_width * (left + right));
+        height = height.saturating_sub(sub_height * (top + bottom));
+    }
+    let bit_depth_luma_minus8 = r.read_ue()?;
+    let bit_depth_chroma_minus8 = r.read_ue()?;
+
+    Ok(Sps {
+        profile,
+        profile_compatibility_flags,
+        constraint_flags,
+        level_idc,
+        max_sub_layers: max_sub_layers_minus1 as u8 + 1,
+        temporal_id_nesting,
+        chroma_format_idc,
+        bit_depth_luma_minus8,
+        bit_depth_chroma_minus8,
+        width,
+        height,
+    })
+}
+
+/// `hvc1` sample entry with the `hvcC` decoder configuration record
+///
+/// Specification: ISO/IEC 14496-15, 8.3.3.1
+///
+/// # Arguments
+///
+/// * `parameter_sets` - VPS, SPS and PPS NAL units of the stream
+pub fn sample_entry(parameter_sets: &[&[u8]]) -> Result<SampleEntry, AppError> {
+    let sps = parameter_sets
+        .iter()
+        .find(|nal| nal_type(nal) == NAL_SPS)
+        .ok_or(AppError::OutOfBounds)?;
+    let sps = parse_sps(sps)?;
+
+    let mut hvcc = vec![1, sps.profile]; // configuration version
+    hvcc.extend_from_slice(&sps.profile_compatibility_flags.to_be_bytes());
+    hvcc.extend_from_slice(&sps.constraint_flags);
+    hvcc.extend_from_slice(&[
+        sps.level_idc,
+        0xf0, // min_spatial_segmentation_idc
+        0x00,
+        0xfc, // parallelismType
+        0xfc | sps.chroma_format_idc as u8,
+        0xf8 | sps.bit_depth_luma_minus8 as u8,
+        0xf8 | sps.bit_depth_chroma_minus8 as u8,
+        0x00, // avgFrameRate
+        0x00,
+        // 4 bytes of NAL unit length
+        sps.max_sub_layers << 3 | sps.temporal_id_nesting << 2 | 3,
+    ]);
+
+    let arrays = [NAL_VPS, NAL_SPS, NAL_PPS]
+        .iter()
+        .map(|tp| {
+            let nals = parameter_sets
+                .iter()
+                .filter(|nal| nal_type(nal) == *tp)
+                .collect::<Vec<&&[u8]>>();
+            (*tp, nals)
+        })
+        .collect::<Vec<(u8, Vec<&&[u8]>)>>();
+    hvcc.push(arrays.len() as u8);
+    for (tp, nals) in arrays {
+        // all parameter sets are in the sample entry
+        hvcc.push(0x80 | tp);
+        hvcc.extend_from_slice(&(nals.len() as u16).to_be_bytes());
+        for nal in nals {
+            hvcc.extend_from_slice(&(nal.len() as u16).to_be_bytes());
+            hvcc.extend_from_slice(nal);
+        }
+    }
+
+    Ok(SampleEntry {
+        kind: *b"hvc1",
+        width: sps.width,
+        height: sps.height,
+        config: mp4_box(b"hvcC", &hvcc),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// SPS of a 1280x720 stream from x265, Main profile, level 3.1
+    const SPS: &[u8] = &[
+        0x42, 0x01, 0x01, 0x01, 0x60, 0x00, 0x00, 0x03, 0x00, 0x90, 0x00, 0x00, 0x03, 0x00, 0x00,
+        0x03, 0x00, 0x5d, 0xa0, 0x02, 0x80, 0x80, 0x2d, 0x16, 0x59, 0x59, 0xa4, 0x93, 0x2b, 0xc0,
+        0x5a, 0x80, 0x80, 0x80, 0x82, 0x00, 0x00, 0x03, 0x00, 0x02, 0x00, 0x00, 0x03, 0x00, 0x32,
+        0x10,
+    ];
+
+    #[test]
+    fn parse_sps_of_main_profile() {
+        let sps = parse_sps(SPS).unwrap();
+        assert_eq!(nal_type(SPS), NAL_SPS);
+        assert_eq!((sps.width, sps.height), (1280, 720));
+        assert_eq!(sps.profile, 1);
+        assert_eq!(sps.profile_compatibility_flags, 0x6000_0000);
+        assert_eq!(sps.constraint_flags, [0x90, 0, 0, 0, 0, 0]);
+        assert_eq!(sps.level_idc, 93);
+        assert_eq!((sps.max_sub_layers, sps.temporal_id_nesting), (1, 1));
+        assert_eq!(sps.chroma_format_idc, 1);
+        assert_eq!(
+            (sps.bit_depth_luma_minus8, sps.bit_depth_chroma_minus8),
+            (0, 0)
+        );
+    }
+
+    #[test]
+    fn parse_truncated_sps() {
+        assert!(parse_sps(&SPS[..1]).is_err());
+        assert!(parse_sps(&SPS[..12]).is_err());
+    }
+}
@@ -0,0 +1,233 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::args::VideoFormat;
+use crate::common::error::AppError;
+use crate::common::mp4::{Mp4Writer, SampleEntry};
+use crate::foxglove_msgs::CompressedVideo;
+
+use self::nal::{length_prefixed, split_annex_b};
+
+mod h264;
+mod h265;
+mod nal;
+
+/// Annex B start code written before the parameter sets from the previous packets
+const START_CODE: [u8; 4] = [0, 0, 0, 1];
+
+/// Video codecs which streams can be extracted without decoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Codec {
+    H264,
+    H265,
+}
+
+impl Codec {
+    fn from_format(format: &str) -> Option<Self> {
+        match format.to_ascii_lowercase().as_str() {
+            "h264" | "avc" => Some(Self::H264),
+            "h265" | "hevc" => Some(Self::H265),
+            _ => None,
+        }
+    }
+
+    /// Extension of the elementary stream files
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::H264 => "h264",
+            Self::H265 => "h265",
+        }
+    }
+
+    fn nal_type(&self, nal: &[u8]) -> u8 {
+        match self {
+            Self::H264 => h264::nal_type(nal),
+            Self::H265 => h265::nal_type(nal),
+        }
+    }
+
+    fn is_keyframe(&self, nal_type: u8) -> bool {
+        match self {
+            Self::H264 => h264::is_keyframe(nal_type),
+            Self::H265 => h265::is_keyframe(nal_type),
+        }
+    }
+
+    fn is_parameter_set(&self, nal_type: u8) -> bool {
+        match self {
+            Self::H264 => h264::is_parameter_set(nal_type),
+            Self::H265 => h265::is_parameter_set(nal_type),
+        }
+    }
+
+    /// Are all parameter sets required to decode the keyframes received?
+    fn has_parameter_sets(&self, parameter_sets: &[Vec<u8>]) -> bool {
+        let required = match self {
+            Self::H264 => h264::PARAMETER_SETS,
+            Self::H265 => h265::PARAMETER_SETS,
+        };
+        required
+            .iter()
+            .all(|tp| parameter_sets.iter().any(|nal| self.nal_type(nal) == *tp))
+    }
+
+    fn is_delimiter(&self, nal_type: u8) -> bool {
+        match self {
+            Self::H264 => h264::is_delimiter(nal_type),
+            Self::H265 => h265::is_delimiter(nal_type),
+        }
+    }
+
+    fn sample_entry(&self, parameter_sets: &[&[u8]]) -> Result<SampleEntry, AppError> {
+        match self {
+            Self::H264 => h264::sample_entry(parameter_sets),
+            Self::H265 => h265::sample_entry(parameter_sets),
+        }
+    }
+}
+
+/// Output file of the video stream
+#[derive(Debug)]
+enum VideoOutput {
+    AnnexB(BufWriter<File>),
+    Mp4(Mp4Writer, SampleEntry),
+}
+
+/// Video stream of the topic, which is written to disk packet by packet.
+///
+/// The stream starts from the first keyframe, because the previous packets
+/// can't be decoded. Parameter sets received until the first keyframe are used
+/// for the whole stream.
+#[derive(Debug)]
+pub struct VideoStream {
+    codec: Codec,
+    format: VideoFormat,
+    /// Path to the output file
+    path: String,
+    /// Parameter sets received before the first keyframe
+    parameter_sets: Vec<Vec<u8>>,
+    output: Option<VideoOutput>,
+}
+
+impl VideoStream {
+    /// # Arguments
+    ///
+    /// * `codec` - video format from the packets, e.g. `h264`
+    /// * `base_path` - path to the output file without extension
+    /// * `format` - output format
+    pub fn new(codec: &str, base_path: &str, format: VideoFormat) -> Result<Self, AppError> {
+        let codec = Codec::from_format(codec)
+            .ok_or_else(|| AppError::UnsupportedVideoFormat(codec.to_string()))?;
+        let extension = match format {
+            VideoFormat::Mp4 => "mp4",
+            VideoFormat::AnnexB => codec.extension(),
+        };
+        Ok(Self {
+            codec,
+            format,
+            path: format!("{}.{}", base_path, extension),
+            parameter_sets: Vec::new(),
+            output: None,
+        })
+    }
+
+    /// Append the packet to the stream.
+    /// Returns `true` if the packet is written.
+    pub fn push(&mut self, packet: &CompressedVideo) -> Result<bool, AppError> {
+        if Codec::from_format(packet.format) != Some(self.codec) {
+            return Err(self.error(format!(
+                "format of the packets changed from `{}` to `{}`",
+                self.codec.extension(),
+                packet.format,
+            )));
+        }
+        let nals = split_annex_b(packet.data);
+        if nals.is_empty() {
+            return Err(self.error("packet is not in Annex B format".to_string()));
+        }
+
+        let codec = self.codec;
+        let keyframe = nals
+            .iter()
+            .any(|nal| codec.is_keyframe(codec.nal_type(nal)));
+        if self.output.is_none() {
+            for nal in &nals {
+                if codec.is_parameter_set(codec.nal_type(nal))
+                    && !self.parameter_sets.iter().any(|known| known == nal)
+                {
+                    self.parameter_sets.push(nal.to_vec());
+                }
+            }
+            if !keyframe || !codec.has_parameter_sets(&self.parameter_sets) {
+                return Ok(false);
+            }
+            self.output = Some(self.create_output(&nals)?);
+        }
+
+        let output = self.output.as_mut().expect("output is created above");
+        let result = match output {
+            VideoOutput::AnnexB(writer) => writer.write_all(packet.data),
+            VideoOutput::Mp4(writer, _) => {
+                let sample = nals
+                    .iter()
+                    .filter(|nal| {
+                        let nal_type = codec.nal_type(nal);
+                        !codec.is_parameter_set(nal_type) && !codec.is_delimiter(nal_type)
+                    })
+                    .flat_map(|nal| length_prefixed(nal))
+                    .collect::<Vec<u8>>();
+                if sample.is_empty() {
+                    return Ok(false);
+                }
+                writer.write_sample(&sample, packet.timestamp.nanos(), keyframe)
+            }
+        };
+        result.map_err(|e| AppError::CannotSave(self.path.clone(), e.to_string()))?;
+        Ok(true)
+    }
+
+    /// Write the rest of the stream and close the file
+    pub fn finish(self) -> Result<(), AppError> {
+        let result = match self.output {
+            Some(VideoOutput::AnnexB(mut writer)) => writer.flush(),
+            Some(VideoOutput::Mp4(writer, entry)) => writer.finish(&entry),
+            None => Ok(()),
+        };
+        result.map_err(|e| AppError::CannotSave(self.path, e.to_string()))
+    }
+
+    /// Create the output file at the first keyframe
+    fn create_output(&self, nals: &[&[u8]]) -> Result<VideoOutput, AppError> {
+        let save_error = |e: std::io::Error| AppError::CannotSave(self.path.clone(), e.to_string());
+        match self.format {
+            VideoFormat::AnnexB => {
+                let mut writer = BufWriter::new(File::create(&self.path).map_err(save_error)?);
+                // parameter sets may be sent before the keyframe
+                for parameter_set in &self.parameter_sets {
+                    if !nals.contains(&parameter_set.as_slice()) {
+                        writer.write_all(&START_CODE).map_err(save_error)?;
+                        writer.write_all(parameter_set).map_err(save_error)?;
+                    }
+                }
+                Ok(VideoOutput::AnnexB(writer))
+            }
+            VideoFormat::Mp4 => {
+                let parameter_sets = self
+                    .parameter_sets
+                    .iter()
+                    .map(|nal| nal.as_slice())
+                    .collect::<Vec<&[u8]>>();
+                let entry = self
+                    .codec
+                    .sample_entry(&parameter_sets)
+                    .map_err(|e| self.error(format!("invalid parameter sets, {}", e)))?;
+                let writer = Mp4Writer::create(&self.path, entry.kind).map_err(save_error)?;
+                Ok(VideoOutput::Mp4(writer, entry))
+            }
+        }
+    }
+
+    fn error(&self, cause: String) -> AppError {
+        AppError::InvalidVideoStream(self.path.clone(), cause)
+    }
+}
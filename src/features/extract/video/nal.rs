@@ -0,0 +1,165 @@
+use crate::common::error::AppError;
+
+/// Split Annex B byte stream into NAL units without start codes
+pub fn split_annex_b(data: &[u8]) -> Vec<&[u8]> {
+    let mut starts = Vec::new();
+    let mut i = 0;
+    while i + 3 <= data.len() {
+        if data[i..i + 3] == [0, 0, 1] {
+            starts.push(i + 3);
+            i += 3;
+        } else {
+            i += 1;
+        }
+    }
+
+    starts
+        .iter()
+        .enumerate()
+        .map(|(n, start)| {
+            let end = starts.get(n + 1).map(|next| next - 3).unwrap_or(data.len());
+            let nal = &data[*start..end];
+            // zero byte of the next 4-byte start code and trailing zeros
+            let trimmed = nal
+                .iter()
+                .rposition(|b| *b != 0)
+                .map(|p| p + 1)
+                .unwrap_or(0);
+            &nal[..trimmed]
+        })
+        .filter(|nal| !nal.is_empty())
+        .collect()
+}
+
+/// Reader of the exp-Golomb coded syntax elements of parameter sets
+///
+/// Specification: ITU-T H.264, 7.2 and 9.1
+pub struct BitReader {
+    /// Raw byte sequence payload without emulation prevention bytes
+    rbsp: Vec<u8>,
+    /// Position in bits
+    pos: usize,
+}
+
+impl BitReader {
+    /// # Arguments
+    ///
+    /// * `payload` - NAL unit without the header
+    pub fn new(payload: &[u8]) -> Self {
+        let mut rbsp = Vec::with_capacity(payload.len());
+        let mut zeros = 0;
+        for byte in payload {
+            // `0x000003` is inserted by encoders to avoid start codes in the payload
+            if zeros >= 2 && *byte == 3 {
+                zeros = 0;
+                continue;
+            }
+            zeros = if *byte == 0 { zeros + 1 } else { 0 };
+            rbsp.push(*byte);
+        }
+        Self { rbsp, pos: 0 }
+    }
+
+    pub fn read_bit(&mut self) -> Result<u32, AppError> {
+        let byte = self.rbsp.get(self.pos / 8).ok_or(AppError::OutOfBounds)?;
+        let bit = (byte >> (7 - self.pos % 8)) & 1;
+        self.pos += 1;
+        Ok(bit as u32)
+    }
+
+    /// Read `n` bits, no more than 32
+    pub fn read_bits(&mut self, n: u32) -> Result<u32, AppError> {
+        let mut value = 0u64;
+        for _ in 0..n {
+            value = (value << 1) | self.read_bit()? as u64;
+        }
+        Ok(value as u32)
+    }
+
+    pub fn skip_bits(&mut self, n: usize) -> Result<(), AppError> {
+        if self.pos + n > self.rbsp.len() * 8 {
+            return Err(AppError::OutOfBounds);
+        }
+        self.pos += n;
+        Ok(())
+    }
+
+    /// Read unsigned exp-Golomb code `ue(v)`
+    pub fn read_ue(&mut self) -> Result<u32, AppError> {
+        let mut leading_zeros = 0;
+        while self.read_bit()? == 0 {
+            leading_zeros += 1;
+            if leading_zeros > 31 {
+                return Err(AppError::OutOfBounds);
+            }
+        }
+        let value = (1u64 << leading_zeros) - 1 + self.read_bits(leading_zeros)? as u64;
+        Ok(value as u32)
+    }
+
+    /// Read signed exp-Golomb code `se(v)`
+    pub fn read_se(&mut self) -> Result<i32, AppError> {
+        let value = self.read_ue()? as i64;
+        Ok(if value % 2 == 1 {
+            (value + 1) / 2
+        } else {
+            -value / 2
+        } as i32)
+    }
+}
+
+/// NAL unit prefixed with its length, as in the MP4 samples
+pub fn length_prefixed(nal: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(nal.len() + 4);
+    data.extend_from_slice(&(nal.len() as u32).to_be_bytes());
+    data.extend_from_slice(nal);
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_annex_b_with_both_start_codes() {
+        let data = [
+            0, 0, 0, 1, 0x67, 0x42, // 4-byte start code
+            0, 0, 1, 0x68, 0xce, // 3-byte start code
+            0, 0, 0, 1, 0x65, 0x88, 0x84, 0, 0, // trailing zeros
+        ];
+        assert_eq!(
+            split_annex_b(&data),
+            vec![&[0x67, 0x42][..], &[0x68, 0xce], &[0x65, 0x88, 0x84]]
+        );
+    }
+
+    #[test]
+    fn split_annex_b_without_start_codes() {
+        assert!(split_annex_b(&[0x65, 0x88, 0x84]).is_empty());
+        assert!(split_annex_b(&[0, 0, 1]).is_empty());
+    }
+
+    #[test]
+    fn remove_emulation_prevention_bytes() {
+        let mut reader = BitReader::new(&[0x00, 0x00, 0x03, 0x01, 0x00, 0x00, 0x03, 0x03]);
+        assert_eq!(reader.read_bits(32).unwrap(), 0x0000_0100);
+        assert_eq!(reader.read_bits(16).unwrap(), 0x0003);
+        assert!(reader.read_bit().is_err());
+    }
+
+    #[test]
+    fn read_exp_golomb_codes() {
+        // 1, 010, 011, 00100, 00101 and padding
+        let mut reader = BitReader::new(&[0b1010_0110, 0b0100_0010, 0b1000_0000]);
+        assert_eq!(reader.read_ue().unwrap(), 0);
+        assert_eq!(reader.read_ue().unwrap(), 1);
+        assert_eq!(reader.read_ue().unwrap(), 2);
+        assert_eq!(reader.read_se().unwrap(), 2);
+        assert_eq!(reader.read_se().unwrap(), -2);
+    }
+
+    #[test]
+    fn prefix_nal_with_length() {
+        assert_eq!(length_prefixed(&[0x65, 0x88]), vec![0, 0, 0, 2, 0x65, 0x88]);
+    }
+}
@@ -10,7 +10,11 @@ pub enum View {
     ExtractedFromTopic(String, u32),
     // Info(String),
     IncompatibleTopicType(String, String, String),
+    NestedImages(Vec<String>),
+    SkippedTopic(String, String),
+    SkippedMessage(String, String),
     NoMessages(String, bool),
+    SupportedEncodings(Vec<String>),
     Error(String),
     Done,
 }
@@ -53,10 +57,23 @@ impl Display for View {
             View::IncompatibleTopicType(topic, actual_type, expected_type) => {
                 writeln!(
                     f,
-                    "Topic {} has incompatible type `{}`, only `{}` is supported",
+                    "Topic {} has incompatible type `{}`, supported types: `{}`",
                     topic, actual_type, expected_type,
                 )
             }
+            View::NestedImages(fields) => {
+                write!(f, "{:i$} but it contains images in fields:", "", i = 12)?;
+                for field in fields {
+                    write!(f, "\n{:i$} - {}", "", field, i = 12)?;
+                }
+                writeln!(f)
+            }
+            View::SkippedTopic(topic, cause) => {
+                writeln!(f, "Topic {} is skipped. {}", topic, cause)
+            }
+            View::SkippedMessage(topic, cause) => {
+                writeln!(f, "Message of topic {} is skipped. {}", topic, cause)
+            }
             View::NoMessages(topic, regex) => {
                 if *regex {
                     writeln!(
@@ -84,6 +101,13 @@ impl Display for View {
                     Ok(())
                 }
             }
+            View::SupportedEncodings(encodings) => {
+                write!(f, "{} encodings:", "Supported".indent().bold().green())?;
+                for encoding in encodings {
+                    write!(f, "\n{:i$} - {}", "", encoding, i = 12)?;
+                }
+                Ok(())
+            }
             View::Error(description) => {
                 write!(f, "\n{} {}\n", "Error".indent().bold().red(), description)
             }
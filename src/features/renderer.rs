@@ -1,5 +1,4 @@
 use std::{
-    collections::BTreeMap,
     fmt::Display,
     io::{stdout, Write},
 };
@@ -12,7 +11,7 @@ use crossterm::{
 
 use crate::features::extract::view::View;
 
-use super::extract::extract::TopicState;
+use super::extract::extract::TopicStates;
 
 /// Because rustc output indent is 12
 const INDENT_SIZE: usize = 12usize;
@@ -30,11 +29,7 @@ impl Indentable for &str {
     /// ```
     fn indent(&self) -> String {
         let len = self.len();
-        let indent = if len <= INDENT_SIZE {
-            INDENT_SIZE - len
-        } else {
-            0usize
-        };
+        let indent = INDENT_SIZE.saturating_sub(len);
         format!("{:indent$}{}", "", &self, indent = indent)
     }
 }
@@ -58,17 +53,17 @@ impl Renderer {
 
     pub fn new_line(&self) {
         let mut stdout = stdout();
-        stdout.write(b"\n").unwrap();
+        stdout.write_all(b"\n").unwrap();
         stdout.flush().unwrap();
     }
 
-    pub fn render(&self, states: &BTreeMap<u32, TopicState>, return_cursor: bool) {
+    pub fn render(&self, states: &TopicStates, return_cursor: bool) {
         let mut stdout = stdout();
         stdout
             .queue(terminal::Clear(ClearType::FromCursorDown))
             .unwrap();
 
-        for (_, state) in states {
+        for state in states.values() {
             let view = if state.extracted == 0 {
                 View::FoundTopic(state.name.clone())
             } else {
@@ -86,7 +81,7 @@ impl Renderer {
                 .queue(cursor::MoveToPreviousLine(lines_number))
                 .unwrap();
         } else {
-            stdout.write(b"\n").unwrap();
+            stdout.write_all(b"\n").unwrap();
         }
         stdout.flush().unwrap();
     }
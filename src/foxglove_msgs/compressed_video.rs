@@ -0,0 +1,41 @@
+use serde::Deserialize;
+
+use crate::common::de::Time;
+
+const ROS_TYPE: &str = "foxglove_msgs/CompressedVideo";
+
+/// A single frame of a compressed video bitstream
+///
+/// Struct definition from:
+/// https://docs.foxglove.dev/docs/visualization/message-schemas/compressed-video
+#[derive(Debug, Deserialize)]
+pub struct CompressedVideo<'a> {
+    /// Timestamp of video frame
+    pub timestamp: Time,
+
+    /// Frame of reference for the video.
+    ///
+    /// The origin of the frame is the optical center of the camera.
+    /// +x points to the right in the video, +y points down, and +z points into the plane of the video.
+    #[allow(dead_code)]
+    pub frame_id: &'a str,
+
+    /// Compressed video frame data.
+    ///
+    /// For packet-based video codecs this data must begin and end on packet boundaries
+    /// (no partial packets), and must contain enough video packets to decode exactly
+    /// one image (either a keyframe or delta frame). H.264 and H.265 data must use
+    /// Annex B formatted data, with parameter sets in the keyframes.
+    pub data: &'a [u8],
+
+    /// Video format.
+    ///
+    /// Supported values: `h264`, `h265`, `vp9`, `av1`
+    pub format: &'a str,
+}
+
+impl<'a> CompressedVideo<'a> {
+    pub fn ros_type() -> &'static str {
+        ROS_TYPE
+    }
+}
@@ -0,0 +1,3 @@
+mod compressed_video;
+
+pub use self::compressed_video::CompressedVideo;
@@ -1,14 +1,24 @@
 use clap::Parser;
 
 use crate::args::Args;
+use crate::features::extract::decode::DecoderRegistry;
 use crate::features::extract::extract as feature_extract;
 
 mod args;
 mod common;
 mod features;
+mod foxglove_msgs;
 mod sensor_msgs;
+mod stereo_msgs;
+mod vision_msgs;
 
 fn main() {
     let args = Args::parse();
-    feature_extract::extract(args);
+    // in-house decoders are registered here, see `DecoderRegistry::register`
+    let decoders = DecoderRegistry::default();
+    if args.list_encodings {
+        feature_extract::list_encodings(&decoders);
+    } else {
+        feature_extract::extract(args, decoders);
+    }
 }
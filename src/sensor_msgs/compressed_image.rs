@@ -0,0 +1,32 @@
+use serde::Deserialize;
+
+use super::Header;
+
+const ROS_TYPE: &str = "sensor_msgs/CompressedImage";
+
+/// This message contains a compressed image
+///
+/// Struct definition from:
+/// http://docs.ros.org/en/noetic/api/sensor_msgs/html/msg/CompressedImage.html
+#[derive(Debug, Deserialize)]
+pub struct CompressedImage<'a> {
+    /// Header timestamp should be acquisition time of image.
+    #[serde(borrow)]
+    pub header: Header<'a>,
+
+    /// Specifies the format of the data
+    ///
+    /// Acceptable values: `jpeg`, `png`.
+    /// image_transport also prepends the encoding of the source image,
+    /// e.g. `bgr8; jpeg compressed bgr8`
+    pub format: &'a str,
+
+    /// Compressed image buffer
+    pub data: &'a [u8],
+}
+
+impl<'a> CompressedImage<'a> {
+    pub fn ros_type() -> &'static str {
+        ROS_TYPE
+    }
+}
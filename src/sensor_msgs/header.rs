@@ -1,14 +1,17 @@
-use crate::common::{cursor::Cursor, error::AppError};
+use serde::Deserialize;
+
+use crate::common::de::Time;
 
 /// Struct definition from:
 /// http://docs.ros.org/en/noetic/api/std_msgs/html/msg/Header.html
-#[derive(Debug)]
+#[derive(Debug, Deserialize)]
 pub struct Header<'a> {
     /// Standard metadata for higher-level stamped data types.
     /// This is generally used to communicate timestamped data
     /// in a particular coordinate frame.
     ///
     /// sequence ID: consecutively increasing ID
+    #[allow(dead_code)]
     pub seq: u32,
 
     /// Two-integer timestamp that is expressed as:
@@ -16,23 +19,9 @@ pub struct Header<'a> {
     /// * stamp.nsec: nanoseconds since stamp_secs (in Python the variable is called 'nsecs')
     ///
     /// time-handling sugar is provided by the client library
-    pub stamp: u64,
+    pub stamp: Time,
 
     /// Frame this data is associated with
+    #[allow(dead_code)]
     pub frame_id: &'a str,
 }
-
-impl<'a> Header<'a> {
-    pub fn from_reader(cursor: &mut Cursor<'a>) -> Result<Self, AppError> {
-        let seq = cursor.next_u32()?;
-        let stamp = cursor.next_time()?;
-        let frame_id =
-            std::str::from_utf8(cursor.next_chunk()?).map_err(|_| AppError::InvalidUtf8String)?;
-
-        Ok(Header {
-            seq,
-            stamp,
-            frame_id,
-        })
-    }
-}
@@ -0,0 +1,117 @@
+//! Values of the `encoding` field of `sensor_msgs/Image`.
+//!
+//! Names are taken from:
+//! https://github.com/ros-perception/vision_opencv/blob/noetic/cv_bridge/include/cv_bridge/image_encodings.h
+
+pub const RGB8: &str = "rgb8";
+pub const BGR8: &str = "bgr8";
+pub const RGBA8: &str = "rgba8";
+pub const BGRA8: &str = "bgra8";
+pub const MONO8: &str = "mono8";
+pub const MONO16: &str = "mono16";
+
+pub const BAYER_RGGB8: &str = "bayer_rggb8";
+pub const BAYER_BGGR8: &str = "bayer_bggr8";
+pub const BAYER_GBRG8: &str = "bayer_gbrg8";
+pub const BAYER_GRBG8: &str = "bayer_grbg8";
+pub const BAYER_RGGB16: &str = "bayer_rggb16";
+pub const BAYER_BGGR16: &str = "bayer_bggr16";
+pub const BAYER_GBRG16: &str = "bayer_gbrg16";
+pub const BAYER_GRBG16: &str = "bayer_grbg16";
+
+/// Packed `U0 Y0 V0 Y1`, the same as `uyvy`
+pub const YUV422: &str = "yuv422";
+/// Packed `Y0 U0 Y1 V0`, the same as `yuyv`
+pub const YUV422_YUY2: &str = "yuv422_yuy2";
+pub const UYVY: &str = "uyvy";
+pub const YUYV: &str = "yuyv";
+
+/// Planar Y followed by interleaved `U V`
+pub const NV12: &str = "nv12";
+/// Planar Y followed by interleaved `V U`
+pub const NV21: &str = "nv21";
+/// Planar Y followed by planar U and planar V
+pub const I420: &str = "i420";
+/// Planar Y followed by planar V and planar U
+pub const YV12: &str = "yv12";
+
+/// Generic OpenCV types, `{depth}C{channels}`, which have no channel semantics
+pub const TYPE_8UC1: &str = "8UC1";
+pub const TYPE_8UC2: &str = "8UC2";
+pub const TYPE_8UC3: &str = "8UC3";
+pub const TYPE_8UC4: &str = "8UC4";
+pub const TYPE_8SC1: &str = "8SC1";
+pub const TYPE_8SC2: &str = "8SC2";
+pub const TYPE_8SC3: &str = "8SC3";
+pub const TYPE_8SC4: &str = "8SC4";
+pub const TYPE_16UC1: &str = "16UC1";
+pub const TYPE_16UC2: &str = "16UC2";
+pub const TYPE_16UC3: &str = "16UC3";
+pub const TYPE_16UC4: &str = "16UC4";
+pub const TYPE_16SC1: &str = "16SC1";
+pub const TYPE_16SC2: &str = "16SC2";
+pub const TYPE_16SC3: &str = "16SC3";
+pub const TYPE_16SC4: &str = "16SC4";
+pub const TYPE_32SC1: &str = "32SC1";
+pub const TYPE_32SC2: &str = "32SC2";
+pub const TYPE_32SC3: &str = "32SC3";
+pub const TYPE_32SC4: &str = "32SC4";
+pub const TYPE_32FC1: &str = "32FC1";
+pub const TYPE_32FC2: &str = "32FC2";
+pub const TYPE_32FC3: &str = "32FC3";
+pub const TYPE_32FC4: &str = "32FC4";
+pub const TYPE_64FC1: &str = "64FC1";
+pub const TYPE_64FC2: &str = "64FC2";
+pub const TYPE_64FC3: &str = "64FC3";
+pub const TYPE_64FC4: &str = "64FC4";
+
+/// Type of the OpenCV matrix element
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CvDepth {
+    U8,
+    S8,
+    U16,
+    S16,
+    S32,
+    F32,
+    F64,
+}
+
+impl CvDepth {
+    /// Size of the element in bytes
+    pub fn size(&self) -> usize {
+        match self {
+            CvDepth::U8 | CvDepth::S8 => 1,
+            CvDepth::U16 | CvDepth::S16 => 2,
+            CvDepth::S32 | CvDepth::F32 => 4,
+            CvDepth::F64 => 8,
+        }
+    }
+}
+
+/// Maximum number of channels in OpenCV matrix (`CV_CN_MAX`)
+pub const CV_CN_MAX: usize = 512;
+
+/// Parse generic OpenCV type like `8UC3`, `32FC1` or `16UC(12)`
+/// into element type and number of channels
+pub fn parse_cv_type(encoding: &str) -> Option<(CvDepth, usize)> {
+    let (depth, channels) = encoding.split_once('C')?;
+    let depth = match depth {
+        "8U" => CvDepth::U8,
+        "8S" => CvDepth::S8,
+        "16U" => CvDepth::U16,
+        "16S" => CvDepth::S16,
+        "32S" => CvDepth::S32,
+        "32F" => CvDepth::F32,
+        "64F" => CvDepth::F64,
+        _ => return None,
+    };
+    let channels = channels
+        .strip_prefix('(')
+        .and_then(|channels| channels.strip_suffix(')'))
+        .unwrap_or(channels);
+    match channels.parse::<usize>() {
+        Ok(channels) if (1..=CV_CN_MAX).contains(&channels) => Some((depth, channels)),
+        _ => None,
+    }
+}
@@ -1,4 +1,12 @@
+mod compressed_image;
 mod header;
 mod image;
+pub mod image_encodings;
+mod point_cloud2;
+pub mod point_field;
+mod region_of_interest;
 
-pub use {self::image::Image, header::Header};
+pub use {
+    self::compressed_image::CompressedImage, self::image::Image, self::point_cloud2::PointCloud2,
+    header::Header, point_field::PointField, region_of_interest::RegionOfInterest,
+};
@@ -0,0 +1,54 @@
+use serde::Deserialize;
+
+use super::{Header, PointField};
+
+const ROS_TYPE: &str = "sensor_msgs/PointCloud2";
+
+/// This message holds a collection of N-dimensional points, which may
+/// contain additional information such as normals, intensity, etc. The
+/// point data is stored as a binary blob, its layout described by the
+/// contents of the "fields" array.
+///
+/// The point cloud data may be organized 2d (image-like) or 1d
+/// (unordered). Point clouds organized as 2d images may be produced by
+/// camera depth sensors such as stereo or time-of-flight.
+///
+/// Struct definition from:
+/// http://docs.ros.org/en/noetic/api/sensor_msgs/html/msg/PointCloud2.html
+#[derive(Debug, Deserialize)]
+pub struct PointCloud2<'a> {
+    /// Time of sensor data acquisition, and the coordinate frame ID (for 3d points).
+    #[serde(borrow)]
+    pub header: Header<'a>,
+
+    /// 2D structure of the point cloud. If the cloud is unordered, height is
+    /// 1 and width is the length of the point cloud.
+    pub height: u32,
+    pub width: u32,
+
+    /// Describes the channels and their layout in the binary data blob.
+    #[serde(borrow)]
+    pub fields: Vec<PointField<'a>>,
+
+    /// Is this data bigendian?
+    pub is_bigendian: bool,
+
+    /// Length of a point in bytes
+    pub point_step: u32,
+
+    /// Length of a row in bytes
+    pub row_step: u32,
+
+    /// Actual point data, size is (row_step*height)
+    pub data: &'a [u8],
+
+    /// True if there are no invalid points
+    #[allow(dead_code)]
+    pub is_dense: bool,
+}
+
+impl<'a> PointCloud2<'a> {
+    pub fn ros_type() -> &'static str {
+        ROS_TYPE
+    }
+}
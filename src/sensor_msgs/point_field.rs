@@ -0,0 +1,44 @@
+use serde::Deserialize;
+
+pub const INT8: u8 = 1;
+pub const UINT8: u8 = 2;
+pub const INT16: u8 = 3;
+pub const UINT16: u8 = 4;
+pub const INT32: u8 = 5;
+pub const UINT32: u8 = 6;
+pub const FLOAT32: u8 = 7;
+pub const FLOAT64: u8 = 8;
+
+/// This message holds the description of one point entry in the
+/// PointCloud2 message format.
+///
+/// Struct definition from:
+/// http://docs.ros.org/en/noetic/api/sensor_msgs/html/msg/PointField.html
+#[derive(Debug, Deserialize)]
+pub struct PointField<'a> {
+    /// Name of field
+    pub name: &'a str,
+
+    /// Offset from start of point struct
+    pub offset: u32,
+
+    /// Datatype enumeration, see above
+    pub datatype: u8,
+
+    /// How many elements in the field
+    #[allow(dead_code)]
+    pub count: u32,
+}
+
+impl<'a> PointField<'a> {
+    /// Size of the field element in bytes, `None` for unknown datatypes
+    pub fn size(&self) -> Option<usize> {
+        match self.datatype {
+            INT8 | UINT8 => Some(1),
+            INT16 | UINT16 => Some(2),
+            INT32 | UINT32 | FLOAT32 => Some(4),
+            FLOAT64 => Some(8),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use serde::Deserialize;
+
+/// This message is used to specify a region of interest within an image
+///
+/// Struct definition from:
+/// http://docs.ros.org/en/noetic/api/sensor_msgs/html/msg/RegionOfInterest.html
+#[derive(Debug, Deserialize)]
+pub struct RegionOfInterest {
+    /// Leftmost pixel of the ROI
+    #[allow(dead_code)]
+    pub x_offset: u32,
+
+    /// Topmost pixel of the ROI
+    #[allow(dead_code)]
+    pub y_offset: u32,
+
+    /// Height of ROI
+    #[allow(dead_code)]
+    pub height: u32,
+
+    /// Width of ROI
+    #[allow(dead_code)]
+    pub width: u32,
+
+    /// True if a distinct rectified ROI should be calculated from the "raw" ROI in this message
+    #[allow(dead_code)]
+    pub do_rectify: bool,
+}
@@ -0,0 +1,54 @@
+use serde::Deserialize;
+
+use crate::sensor_msgs::{Header, Image, RegionOfInterest};
+
+const ROS_TYPE: &str = "stereo_msgs/DisparityImage";
+
+/// This message contains a disparity image from the stereo pipeline
+///
+/// Struct definition from:
+/// http://docs.ros.org/en/noetic/api/stereo_msgs/html/msg/DisparityImage.html
+#[derive(Debug, Deserialize)]
+pub struct DisparityImage<'a> {
+    /// Separate header for compatibility with current TimeSynchronizer.
+    /// Likely to be removed in a later release, use image.header instead.
+    #[serde(borrow)]
+    pub header: Header<'a>,
+
+    /// Floating point disparity image. The disparities are pre-adjusted for any
+    /// x-offset between the principal points of the two cameras (in the case
+    /// that they are verged). That is: d = x_l - x_r - (cx_l - cx_r)
+    #[serde(borrow)]
+    pub image: Image<'a>,
+
+    /// Focal length, pixels
+    pub f: f32,
+
+    /// Baseline, world units
+    pub t: f32,
+
+    /// Subwindow of (potentially) valid disparity values.
+    #[allow(dead_code)]
+    pub valid_window: RegionOfInterest,
+
+    /// The range of disparities searched.
+    /// In the disparity image, any disparity less than min_disparity is invalid.
+    /// The disparity search range defines the horopter, or 3D volume that the
+    /// stereo algorithm can "see". Points with Z outside of:
+    ///     Z_min = fT / max_disparity
+    ///     Z_max = fT / min_disparity
+    /// could not be found.
+    pub min_disparity: f32,
+    pub max_disparity: f32,
+
+    /// Smallest allowed disparity increment. The smallest achievable depth range
+    /// resolution is delta_Z = (Z^2/fT)*delta_d.
+    #[allow(dead_code)]
+    pub delta_d: f32,
+}
+
+impl<'a> DisparityImage<'a> {
+    pub fn ros_type() -> &'static str {
+        ROS_TYPE
+    }
+}
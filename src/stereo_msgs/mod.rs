@@ -0,0 +1,3 @@
+mod disparity_image;
+
+pub use self::disparity_image::DisparityImage;
@@ -0,0 +1,118 @@
+use crate::common::de::Time;
+use crate::common::msg_def::Value;
+
+const ROS_TYPE: &str = "vision_msgs/Detection2DArray";
+
+/// A list of 2D detections, for a multi-object 2D detector.
+///
+/// Layout of the message differs between versions of `vision_msgs`, so it's
+/// read from the message decoded with the definition from the bag. Supported versions:
+/// * ROS1 Noetic, with `int64 id` or `string id` of the hypothesis
+///   and `geometry_msgs/Pose2D` center of the bounding box
+/// * ROS2-like, with `hypothesis.class_id` and `center.position` of the bounding box
+///
+/// Only the fields required to draw and export the bounding boxes are kept.
+///
+/// Struct definition from:
+/// http://docs.ros.org/en/noetic/api/vision_msgs/html/msg/Detection2DArray.html
+#[derive(Debug, Clone)]
+pub struct Detection2DArray {
+    /// Stamp of the image the detections were made on
+    pub stamp: Time,
+
+    /// A list of the detected proposals. A multi-proposal detector might generate
+    /// this list with many candidate detections generated from a single input.
+    pub detections: Vec<Detection2D>,
+}
+
+/// Specifies a 2D detection with the axis-aligned bounding box
+#[derive(Debug, Clone)]
+pub struct Detection2D {
+    /// Class probability of the most likely class, `None` if the detection has no results
+    pub hypothesis: Option<ObjectHypothesis>,
+
+    /// Position of the bounding box center in pixels
+    pub center_x: f64,
+    pub center_y: f64,
+
+    /// Total size of the bounding box in pixels, surrounding the object's center pose
+    pub size_x: f64,
+    pub size_y: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ObjectHypothesis {
+    /// The unique ID of the object class
+    pub class_id: String,
+
+    /// The probability or confidence value of the detected object
+    pub score: f64,
+}
+
+impl Detection2DArray {
+    pub fn ros_type() -> &'static str {
+        ROS_TYPE
+    }
+
+    /// Read the detections from the decoded message.
+    /// Returns the name of the missing field if the layout is not supported.
+    pub fn from_value(message: &Value) -> Result<Self, String> {
+        let stamp = message
+            .get_path("header.stamp")
+            .and_then(Value::as_time)
+            .ok_or("header.stamp")?;
+        let detections = message
+            .get("detections")
+            .and_then(Value::as_array)
+            .ok_or("detections")?
+            .iter()
+            .map(Detection2D::from_value)
+            .collect::<Result<Vec<Detection2D>, String>>()?;
+        Ok(Self { stamp, detections })
+    }
+}
+
+impl Detection2D {
+    fn from_value(detection: &Value) -> Result<Self, String> {
+        let number = |paths: &[&str]| {
+            paths
+                .iter()
+                .find_map(|path| detection.get_path(path).and_then(Value::as_f64))
+                .ok_or_else(|| paths[0].to_string())
+        };
+        let hypothesis = detection
+            .get("results")
+            .and_then(Value::as_array)
+            .ok_or("results")?
+            .iter()
+            .map(ObjectHypothesis::from_value)
+            .collect::<Result<Vec<ObjectHypothesis>, String>>()?
+            .into_iter()
+            .max_by(|a, b| a.score.total_cmp(&b.score));
+        Ok(Self {
+            hypothesis,
+            center_x: number(&["bbox.center.x", "bbox.center.position.x"])?,
+            center_y: number(&["bbox.center.y", "bbox.center.position.y"])?,
+            size_x: number(&["bbox.size_x"])?,
+            size_y: number(&["bbox.size_y"])?,
+        })
+    }
+}
+
+impl ObjectHypothesis {
+    fn from_value(result: &Value) -> Result<Self, String> {
+        // ROS2-like versions nest the class and score in `hypothesis`
+        let hypothesis = result.get("hypothesis").unwrap_or(result);
+        let class_id = match hypothesis.get("class_id").or_else(|| hypothesis.get("id")) {
+            Some(Value::String(id)) => id.to_string(),
+            Some(Value::Int(id)) => id.to_string(),
+            Some(Value::UInt(id)) => id.to_string(),
+            _ => return Err("results.id".to_string()),
+        };
+        let score = hypothesis
+            .get("score")
+            .and_then(Value::as_f64)
+            .ok_or("results.score")?;
+        Ok(Self { class_id, score })
+    }
+}
@@ -0,0 +1,3 @@
+mod detection_2d_array;
+
+pub use self::detection_2d_array::{Detection2D, Detection2DArray};